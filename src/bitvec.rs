@@ -1,6 +1,6 @@
 use ::std::fmt;
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 /// A fixed size bit vec
 pub struct BitVec32(u32);
 
@@ -77,6 +77,84 @@ impl Iterator for BitVec32Iter {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+/// A fixed size bit vec, wide enough for the minutes of an hour
+pub struct BitVec64(u64);
+
+impl BitVec64 {
+    pub const NO_SET_BITS: usize = 64;
+
+    pub fn new() -> BitVec64 {
+        BitVec64(0)
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        let mask = 1 << i;
+        self.0 & mask == mask
+    }
+
+    pub fn set(&mut self, i: usize) {
+        let mask = 1 << i;
+        self.0 |= mask
+    }
+
+    /// Set every bit in `start..end`
+    pub fn set_range(&mut self, start: usize, end: usize) {
+        for i in start..end {
+            self.set(i);
+        }
+    }
+
+    pub fn clear(&mut self, i: usize) {
+        let mask = !(1 << i);
+        self.0 &= mask
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn iter(&self) -> BitVec64Iter {
+        BitVec64Iter(*self)
+    }
+
+    /// The first set bit. A value of [BitVec64::NO_SET_BITS] indicates no bits set.
+    pub fn first(&self) -> usize {
+        self.0.trailing_zeros() as usize
+    }
+
+    pub fn to_u64(self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Debug for BitVec64 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BitVec64({:064b})", self.0)
+    }
+}
+
+pub struct BitVec64Iter(BitVec64);
+
+impl Iterator for BitVec64Iter {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<<Self as Iterator>::Item> {
+        let lsb = self.0.first();
+        match lsb {
+            BitVec64::NO_SET_BITS => None,
+            _ => {
+                self.0.clear(lsb);
+                Some(lsb)
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -141,4 +219,24 @@ mod tests {
         let expected: Vec<usize> = vec![0, 31];
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn bitvec64() {
+        let mut bs = BitVec64::new();
+        assert!(bs.is_empty());
+
+        bs.set_range(5, 8);
+        bs.set(63);
+        assert_eq!(4, bs.len());
+        assert!(bs.get(5) && bs.get(7) && bs.get(63));
+        assert!(!bs.get(8));
+        assert_eq!(0x8000_0000_0000_00e0, bs.to_u64());
+
+        let actual: Vec<usize> = bs.iter().collect();
+        assert_eq!(vec![5, 6, 7, 63], actual);
+
+        bs.clear(63);
+        assert_eq!(3, bs.len());
+        assert_eq!(BitVec64::NO_SET_BITS, BitVec64::new().first());
+    }
 }
\ No newline at end of file
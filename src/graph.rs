@@ -0,0 +1,415 @@
+//! Generic graph searches over implicit graphs
+//!
+//! Graphs are never materialised: each search takes a start node and a closure yielding the
+//! neighbours of a node, so the same code works for [Grid] coordinates, [BitVec32] node sets or
+//! anything else that is `Clone + Eq + Hash`.
+
+use ::std::cmp::{Ordering, Reverse};
+use ::std::collections::{BinaryHeap, HashMap, VecDeque};
+use ::std::hash::Hash;
+
+/// The unweighted distance from [start] to every reachable node
+pub fn bfs_distances<N, F, I>(start: N, mut neighbours: F) -> HashMap<N, usize>
+    where N: Clone + Eq + Hash,
+          F: FnMut(&N) -> I,
+          I: IntoIterator<Item=N>
+{
+    let mut distances = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    distances.insert(start.clone(), 0);
+    queue.push_back(start);
+
+    while let Some(node) = queue.pop_front() {
+        let next_distance = distances[&node] + 1;
+
+        for next in neighbours(&node) {
+            if !distances.contains_key(&next) {
+                distances.insert(next.clone(), next_distance);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    distances
+}
+
+/// The shortest path (by edge count) from [start] to the first node satisfying [is_goal]
+///
+/// The path includes both [start] and the goal. Ties are broken by the order [neighbours] yields
+/// nodes, so yielding them in reading order gives the reading order path.
+pub fn bfs<N, F, I, G>(start: N, mut neighbours: F, mut is_goal: G) -> Option<Vec<N>>
+    where N: Clone + Eq + Hash,
+          F: FnMut(&N) -> I,
+          I: IntoIterator<Item=N>,
+          G: FnMut(&N) -> bool
+{
+    let mut came_from: HashMap<N, Option<N>> = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    came_from.insert(start.clone(), None);
+    queue.push_back(start);
+
+    while let Some(node) = queue.pop_front() {
+        if is_goal(&node) {
+            return Some(reconstruct_path(&came_from, node));
+        }
+
+        for next in neighbours(&node) {
+            if !came_from.contains_key(&next) {
+                came_from.insert(next.clone(), Some(node.clone()));
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+/// The cheapest path from [start] to the first node satisfying [is_goal], and its total cost
+///
+/// [neighbours] yields `(node, edge_cost)` pairs.
+pub fn dijkstra<N, F, I, G>(start: N, neighbours: F, is_goal: G) -> Option<(Vec<N>, usize)>
+    where N: Clone + Eq + Hash,
+          F: FnMut(&N) -> I,
+          I: IntoIterator<Item=(N, usize)>,
+          G: FnMut(&N) -> bool
+{
+    astar(start, neighbours, |_| 0, is_goal)
+}
+
+/// The cheapest path from [start] to the first node satisfying [is_goal], and its total cost
+///
+/// [heuristic] must never overestimate the remaining cost to a goal, otherwise the returned path
+/// may not be the cheapest.
+pub fn astar<N, F, I, H, G>(start: N, mut neighbours: F, mut heuristic: H, mut is_goal: G)
+                            -> Option<(Vec<N>, usize)>
+    where N: Clone + Eq + Hash,
+          F: FnMut(&N) -> I,
+          I: IntoIterator<Item=(N, usize)>,
+          H: FnMut(&N) -> usize,
+          G: FnMut(&N) -> bool
+{
+    let mut came_from: HashMap<N, Option<N>> = HashMap::new();
+    let mut costs: HashMap<N, usize> = HashMap::new();
+    let mut open = BinaryHeap::new();
+
+    // the sequence number makes the heap FIFO among equal estimates, keeping results deterministic
+    let mut seq = 0;
+
+    came_from.insert(start.clone(), None);
+    costs.insert(start.clone(), 0);
+    open.push(Open { estimate: heuristic(&start), seq, cost: 0, node: start });
+
+    while let Some(Open { cost, node, .. }) = open.pop() {
+        if cost > costs[&node] {
+            // stale entry, a cheaper route to this node was already expanded
+            continue;
+        }
+
+        if is_goal(&node) {
+            return Some((reconstruct_path(&came_from, node), cost));
+        }
+
+        for (next, edge_cost) in neighbours(&node) {
+            let next_cost = cost + edge_cost;
+
+            let improved = match costs.get(&next) {
+                Some(&known) => next_cost < known,
+                None => true,
+            };
+
+            if improved {
+                seq += 1;
+                costs.insert(next.clone(), next_cost);
+                came_from.insert(next.clone(), Some(node.clone()));
+                open.push(Open { estimate: next_cost + heuristic(&next), seq, cost: next_cost, node: next });
+            }
+        }
+    }
+
+    None
+}
+
+/// Order [nodes] so every edge `(before, after)` has `before` first
+///
+/// When several nodes are ready at once the smallest is taken first, making the order
+/// lexicographically stable. Returns [None] if the edges contain a cycle.
+pub fn topological_sort<N, E>(nodes: impl IntoIterator<Item=N>, edges: E) -> Option<Vec<N>>
+    where N: Clone + Ord + Hash,
+          E: IntoIterator<Item=(N, N)>
+{
+    let mut in_degree: HashMap<N, usize> = HashMap::new();
+    let mut successors: HashMap<N, Vec<N>> = HashMap::new();
+
+    for node in nodes {
+        in_degree.entry(node).or_insert(0);
+    }
+
+    for (before, after) in edges {
+        in_degree.entry(before.clone()).or_insert(0);
+        *in_degree.entry(after.clone()).or_insert(0) += 1;
+        successors.entry(before).or_default().push(after);
+    }
+
+    let mut ready: BinaryHeap<Reverse<N>> = in_degree.iter()
+        .filter(|&(_, &degree)| degree == 0)
+        .map(|(node, _)| Reverse(node.clone()))
+        .collect();
+
+    let mut sorted = Vec::with_capacity(in_degree.len());
+
+    while let Some(Reverse(node)) = ready.pop() {
+        if let Some(nexts) = successors.get(&node) {
+            for next in nexts {
+                let degree = in_degree.get_mut(next).unwrap();
+                *degree -= 1;
+
+                if *degree == 0 {
+                    ready.push(Reverse(next.clone()));
+                }
+            }
+        }
+
+        sorted.push(node);
+    }
+
+    if sorted.len() == in_degree.len() {
+        Some(sorted)
+    } else {
+        None
+    }
+}
+
+// Helpers
+// =======
+
+fn reconstruct_path<N: Clone + Eq + Hash>(came_from: &HashMap<N, Option<N>>, goal: N) -> Vec<N> {
+    let mut path = vec![goal];
+
+    while let Some(Some(prev)) = path.last().map(|node| &came_from[node]) {
+        path.push(prev.clone());
+    }
+
+    path.reverse();
+    path
+}
+
+/// An entry in the A* open set, ordered so the [BinaryHeap] pops the lowest estimate first
+struct Open<N> {
+    estimate: usize,
+    seq: usize,
+    cost: usize,
+    node: N,
+}
+
+impl<N> PartialEq for Open<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<N> Eq for Open<N> {}
+
+impl<N> PartialOrd for Open<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<N> Ord for Open<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.estimate, other.seq).cmp(&(self.estimate, self.seq))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::bitvec::BitVec32;
+    use ::grid::{Coord, Grid};
+    use ::math::SubAbs;
+
+    const MAZE: &str = "\
+S..#...
+.#.#.#.
+.#...#G\
+";
+
+    fn parse_maze(maze: &str) -> (Grid<bool>, Coord, Coord) {
+        let lines: Vec<&str> = maze.lines().collect();
+        let mut grid = Grid::new(lines[0].len(), lines.len(), false);
+        let mut start = (0, 0);
+        let mut goal = (0, 0);
+
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                grid[(x, y)] = c != '#';
+                match c {
+                    'S' => start = (x, y),
+                    'G' => goal = (x, y),
+                    _ => {}
+                }
+            }
+        }
+
+        (grid, start, goal)
+    }
+
+    fn open_neighbours<'a>(grid: &'a Grid<bool>) -> impl FnMut(&Coord) -> Vec<Coord> + 'a {
+        move |&c| grid.neighbours(c).filter(|&n| grid[n]).collect()
+    }
+
+    fn manhattan(a: Coord, b: Coord) -> usize {
+        a.0.sub_abs(b.0) + a.1.sub_abs(b.1)
+    }
+
+    #[test]
+    fn bfs_finds_shortest_grid_path() {
+        let (grid, start, goal) = parse_maze(MAZE);
+
+        let path = bfs(start, open_neighbours(&grid), |&c| c == goal).unwrap();
+
+        assert_eq!(start, path[0]);
+        assert_eq!(goal, *path.last().unwrap());
+        assert_eq!(13, path.len());
+    }
+
+    #[test]
+    fn bfs_prefers_reading_order() {
+        let grid = Grid::new(3, 3, true);
+
+        // both (1, 0) and (0, 1) lead to (1, 1) in two steps, up/left is yielded first
+        let path = bfs((0, 0), open_neighbours(&grid), |&c| c == (1, 1)).unwrap();
+
+        assert_eq!(vec![(0, 0), (1, 0), (1, 1)], path);
+    }
+
+    #[test]
+    fn bfs_returns_none_when_unreachable() {
+        let (mut grid, start, goal) = parse_maze(MAZE);
+        grid[(6, 1)] = false;
+
+        assert_eq!(None, bfs(start, open_neighbours(&grid), |&c| c == goal));
+    }
+
+    #[test]
+    fn bfs_distances_covers_reachable_nodes() {
+        let (grid, start, goal) = parse_maze(MAZE);
+
+        let distances = bfs_distances(start, open_neighbours(&grid));
+
+        assert_eq!(Some(&0), distances.get(&start));
+        assert_eq!(Some(&12), distances.get(&goal));
+        assert_eq!(None, distances.get(&(3, 0)));
+        assert_eq!(grid.coords().filter(|&c| grid[c]).count(), distances.len());
+    }
+
+    #[test]
+    fn bfs_over_bitvec_sets() {
+        // nodes are subsets of {0, 1, 2, 3}, edges add a single element
+        let full = {
+            let mut bv = BitVec32::new();
+            (0..4).for_each(|i| bv.set(i));
+            bv
+        };
+
+        let neighbours = |set: &BitVec32| {
+            (0..4)
+                .filter(|&i| !set.get(i))
+                .map(|i| {
+                    let mut next = set.clone();
+                    next.set(i);
+                    next
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let path = bfs(BitVec32::new(), neighbours, |set| *set == full).unwrap();
+        assert_eq!(5, path.len());
+
+        let distances = bfs_distances(BitVec32::new(), neighbours);
+        assert_eq!(16, distances.len());
+        assert_eq!(Some(&4), distances.get(&full));
+    }
+
+    #[test]
+    fn dijkstra_finds_cheapest_path() {
+        //   a --1-- b --1-- d
+        //   |               |
+        //   +------5--------+
+        //   |
+        //   +--2-- c --1----+
+        let edges = |n: &char| -> Vec<(char, usize)> {
+            match *n {
+                'a' => vec![('b', 1), ('d', 5), ('c', 2)],
+                'b' => vec![('d', 1)],
+                'c' => vec![('d', 1)],
+                _ => vec![],
+            }
+        };
+
+        let (path, cost) = dijkstra('a', edges, |&n| n == 'd').unwrap();
+
+        assert_eq!(vec!['a', 'b', 'd'], path);
+        assert_eq!(2, cost);
+
+        assert_eq!(None, dijkstra('b', edges, |&n| n == 'a'));
+    }
+
+    #[test]
+    fn astar_agrees_with_dijkstra_on_grid() {
+        // entering a cell costs its digit
+        let costs = "\
+1163751
+1381373
+2136511
+3694931
+7463417\
+";
+        let lines: Vec<&[u8]> = costs.lines().map(str::as_bytes).collect();
+        let mut grid = Grid::new(lines[0].len(), lines.len(), 0);
+        for c in grid.coords().collect::<Vec<_>>() {
+            grid[c] = (lines[c.1][c.0] - b'0') as usize;
+        }
+
+        let goal = (grid.width() - 1, grid.height() - 1);
+        let neighbours = |&c: &Coord| grid.neighbours(c).map(|n| (n, grid[n])).collect::<Vec<_>>();
+
+        let (d_path, d_cost) = dijkstra((0, 0), neighbours, |&c| c == goal).unwrap();
+        let (a_path, a_cost) = astar((0, 0), neighbours, |&c| manhattan(c, goal), |&c| c == goal).unwrap();
+
+        assert_eq!(d_cost, a_cost);
+        assert_eq!(d_cost, d_path[1..].iter().map(|&c| grid[c]).sum::<usize>());
+        assert_eq!(a_cost, a_path[1..].iter().map(|&c| grid[c]).sum::<usize>());
+    }
+
+    #[test]
+    fn topological_sort_is_lexicographic() {
+        // the day 7 sample
+        let edges = vec![
+            ('C', 'A'),
+            ('C', 'F'),
+            ('A', 'B'),
+            ('A', 'D'),
+            ('B', 'E'),
+            ('D', 'E'),
+            ('F', 'E'),
+        ];
+
+        let order: String = topological_sort(vec![], edges).unwrap().into_iter().collect();
+        assert_eq!("CABDFE", order);
+    }
+
+    #[test]
+    fn topological_sort_includes_isolated_nodes() {
+        let order = topological_sort(vec![3, 1, 2], vec![(2, 1)]).unwrap();
+        assert_eq!(vec![2, 1, 3], order);
+    }
+
+    #[test]
+    fn topological_sort_detects_cycles() {
+        let edges = vec![('a', 'b'), ('b', 'c'), ('c', 'a'), ('x', 'a')];
+        assert_eq!(None, topological_sort(vec![], edges));
+    }
+}
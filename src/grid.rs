@@ -0,0 +1,149 @@
+use ::std::ops::{Index, IndexMut};
+
+/// An (x, y) cell coordinate. x grows to the right and y grows downward.
+pub type Coord = (usize, usize);
+
+/// A dense, fixed size, row major 2D grid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Grid<T> {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, (x, y): Coord) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, coord: Coord) -> Option<&T> {
+        if self.contains(coord) {
+            Some(&self.cells[self.index_of(coord)])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, coord: Coord) -> Option<&mut T> {
+        if self.contains(coord) {
+            let i = self.index_of(coord);
+            Some(&mut self.cells[i])
+        } else {
+            None
+        }
+    }
+
+    /// All coordinates in reading order (top to bottom, left to right)
+    pub fn coords(&self) -> impl Iterator<Item=Coord> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    /// The orthogonally adjacent coordinates that lie inside the grid, in reading order
+    pub fn neighbours(&self, (x, y): Coord) -> impl Iterator<Item=Coord> {
+        let (w, h) = (self.width, self.height);
+
+        let up = if y > 0 { Some((x, y - 1)) } else { None };
+        let left = if x > 0 { Some((x - 1, y)) } else { None };
+        let right = if x + 1 < w { Some((x + 1, y)) } else { None };
+        let down = if y + 1 < h { Some((x, y + 1)) } else { None };
+
+        up.into_iter().chain(left).chain(right).chain(down)
+    }
+
+    /// A grid of the same size with [f] applied to every cell
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    fn index_of(&self, (x, y): Coord) -> usize {
+        y * self.width + x
+    }
+}
+
+impl<T> Index<Coord> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, coord: Coord) -> &T {
+        assert!(self.contains(coord), "coord {:?} out of bounds", coord);
+        &self.cells[self.index_of(coord)]
+    }
+}
+
+impl<T> IndexMut<Coord> for Grid<T> {
+    fn index_mut(&mut self, coord: Coord) -> &mut T {
+        assert!(self.contains(coord), "coord {:?} out of bounds", coord);
+        let i = self.index_of(coord);
+        &mut self.cells[i]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index() {
+        let mut grid = Grid::new(3, 2, 0u8);
+        grid[(2, 1)] = 7;
+
+        assert_eq!(7, grid[(2, 1)]);
+        assert_eq!(Some(&7), grid.get((2, 1)));
+        assert_eq!(None, grid.get((3, 1)));
+        assert_eq!(None, grid.get((0, 2)));
+    }
+
+    #[test]
+    fn neighbours_are_in_reading_order() {
+        let grid = Grid::new(3, 3, ());
+
+        let actual: Vec<Coord> = grid.neighbours((1, 1)).collect();
+        assert_eq!(vec![(1, 0), (0, 1), (2, 1), (1, 2)], actual);
+
+        let actual: Vec<Coord> = grid.neighbours((0, 0)).collect();
+        assert_eq!(vec![(1, 0), (0, 1)], actual);
+
+        let actual: Vec<Coord> = grid.neighbours((2, 2)).collect();
+        assert_eq!(vec![(2, 1), (1, 2)], actual);
+    }
+
+    #[test]
+    fn map() {
+        let mut grid = Grid::new(2, 2, 1);
+        grid[(1, 1)] = 5;
+
+        let doubled = grid.map(|&n| n * 2);
+        assert_eq!(2, doubled[(0, 0)]);
+        assert_eq!(10, doubled[(1, 1)]);
+    }
+
+    #[test]
+    fn coords_are_in_reading_order() {
+        let grid = Grid::new(2, 2, ());
+
+        let actual: Vec<Coord> = grid.coords().collect();
+        assert_eq!(vec![(0, 0), (1, 0), (0, 1), (1, 1)], actual);
+    }
+}
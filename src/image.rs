@@ -0,0 +1,234 @@
+//! Minimal image output for visualising puzzle grids
+//!
+//! Supports binary PPM (P6) and PNG. PNG data is stored uncompressed (deflate "stored" blocks) so
+//! no compression library is needed; files are large but any viewer can open them.
+
+use ::grid::Grid;
+use ::std::fs::File;
+use ::std::io::{self, BufWriter, Write};
+use ::std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0, 0, 0);
+    pub const WHITE: Rgb = Rgb(255, 255, 255);
+
+    /// Blend towards [other], [amount] of 0 is [self] and 255 is [other]
+    pub fn mix(self, other: Rgb, amount: u8) -> Rgb {
+        let mix = |a: u8, b: u8| ((a as u16 * (255 - amount as u16) + b as u16 * amount as u16) / 255) as u8;
+        Rgb(mix(self.0, other.0), mix(self.1, other.1), mix(self.2, other.2))
+    }
+}
+
+/// A distinct colour for each index, spreading hues by the golden angle
+pub fn palette(i: usize) -> Rgb {
+    let hue = (i as f64 * 137.507_764) % 360.0;
+    hsv_to_rgb(hue, 0.65, 0.9)
+}
+
+/// Write [image] to [path], picking the format from the extension (`.png`, otherwise PPM)
+pub fn save(image: &Grid<Rgb>, path: &Path) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+
+    let is_png = path.extension()
+        .map(|ext| ext.eq_ignore_ascii_case("png"))
+        .unwrap_or(false);
+
+    if is_png {
+        write_png(image, &mut out)?;
+    } else {
+        write_ppm(image, &mut out)?;
+    }
+
+    out.flush()
+}
+
+pub fn write_ppm(image: &Grid<Rgb>, out: &mut impl Write) -> io::Result<()> {
+    write!(out, "P6\n{} {}\n255\n", image.width(), image.height())?;
+
+    for c in image.coords() {
+        let Rgb(r, g, b) = image[c];
+        out.write_all(&[r, g, b])?;
+    }
+
+    Ok(())
+}
+
+pub fn write_png(image: &Grid<Rgb>, out: &mut impl Write) -> io::Result<()> {
+    const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+    out.write_all(&SIGNATURE)?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(image.width() as u32).to_be_bytes());
+    header.extend_from_slice(&(image.height() as u32).to_be_bytes());
+    // bit depth 8, colour type 2 (truecolour), default compression, filter and no interlace
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(out, b"IHDR", &header)?;
+
+    // each scanline is prefixed by its filter type, 0 is none
+    let mut raw = Vec::with_capacity(image.height() * (1 + image.width() * 3));
+    for y in 0..image.height() {
+        raw.push(0);
+        for x in 0..image.width() {
+            let Rgb(r, g, b) = image[(x, y)];
+            raw.extend_from_slice(&[r, g, b]);
+        }
+    }
+    write_chunk(out, b"IDAT", &zlib_stored(&raw))?;
+
+    write_chunk(out, b"IEND", &[])
+}
+
+// Helpers
+// =======
+
+fn write_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+
+    let crc = crc32(kind.iter().chain(data.iter()));
+    out.write_all(&crc.to_be_bytes())
+}
+
+/// Wrap [data] in a zlib stream made of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 0xffff;
+
+    let mut out = Vec::with_capacity(data.len() + data.len() / MAX_BLOCK * 5 + 11);
+
+    // CM 8 (deflate), 32K window, no dictionary, fastest; the header must be a multiple of 31
+    out.extend_from_slice(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0x00, 0x00, 0xff, 0xff]);
+    }
+
+    while let Some(block) = blocks.next() {
+        let is_final = blocks.peek().is_none();
+        let len = block.len() as u16;
+
+        out.push(is_final as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32<'a>(bytes: impl Iterator<Item=&'a u8>) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+
+    let (mut a, mut b) = (1u32, 0u32);
+
+    for &byte in bytes {
+        a = (a + byte as u32) % MOD_ADLER;
+        b = (b + a) % MOD_ADLER;
+    }
+
+    (b << 16) | a
+}
+
+fn hsv_to_rgb(hue: f64, saturation: f64, value: f64) -> Rgb {
+    let c = value * saturation;
+    let h = hue / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+
+    let m = value - c;
+    let to_byte = |v: f64| ((v + m) * 255.0).round() as u8;
+
+    Rgb(to_byte(r), to_byte(g), to_byte(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_is_correct() {
+        assert_eq!(0xae42_6082, crc32(b"IEND".iter()));
+        assert_eq!(0xcbf4_3926, crc32(b"123456789".iter()));
+    }
+
+    #[test]
+    fn adler32_is_correct() {
+        assert_eq!(0x11e6_0398, adler32(b"Wikipedia"));
+        assert_eq!(1, adler32(b""));
+    }
+
+    #[test]
+    fn zlib_stored_splits_blocks() {
+        let data = vec![7u8; 0x10000];
+        let z = zlib_stored(&data);
+
+        // header + two block headers + data + checksum
+        assert_eq!(2 + 5 + 5 + data.len() + 4, z.len());
+        assert_eq!(0, z[2]);
+        assert_eq!(1, z[2 + 5 + 0xffff]);
+    }
+
+    #[test]
+    fn ppm_layout() {
+        let mut image = Grid::new(2, 1, Rgb::BLACK);
+        image[(1, 0)] = Rgb(1, 2, 3);
+
+        let mut out = Vec::new();
+        write_ppm(&image, &mut out).unwrap();
+
+        assert_eq!(b"P6\n2 1\n255\n\0\0\0\x01\x02\x03".to_vec(), out);
+    }
+
+    #[test]
+    fn png_layout() {
+        let image = Grid::new(3, 2, Rgb::WHITE);
+
+        let mut out = Vec::new();
+        write_png(&image, &mut out).unwrap();
+
+        assert_eq!(b"\x89PNG\r\n\x1a\n", &out[..8]);
+        assert_eq!(b"IHDR", &out[12..16]);
+        assert_eq!([0, 0, 0, 3, 0, 0, 0, 2], out[16..24]);
+        assert_eq!(b"\0\0\0\0IEND\xae\x42\x60\x82", &out[out.len() - 12..]);
+    }
+
+    #[test]
+    fn palette_is_distinct() {
+        let colours: Vec<Rgb> = (0..50).map(palette).collect();
+
+        for (i, a) in colours.iter().enumerate() {
+            for b in &colours[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+    }
+}
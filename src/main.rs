@@ -2,9 +2,19 @@
 
 extern crate bit_set;
 
+#[allow(dead_code)]
 mod bitvec;
+#[allow(dead_code)]
 mod trie;
 mod math;
+#[allow(dead_code)]
+mod grid;
+#[allow(dead_code)]
+mod graph;
+mod image;
+mod runner;
+#[cfg(feature = "parallel")]
+mod parallel;
 
 mod problem01;
 mod problem02;
@@ -13,42 +23,292 @@ mod problem04;
 mod problem05;
 mod problem06;
 
-fn main() {
-    do_problem(problem01::part1);
-    do_problem(problem01::part2);
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter};
+use std::path::Path;
+use std::process::{self, Command};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use runner::{Outcome, Task};
+
+const USAGE: &str = "\
+usage: aoc2018                           run every problem
+       aoc2018 run [--jobs N | --isolate] run every problem on N threads, or each in its own process
+       aoc2018 render <day> <out>        render a day's grid to <out> (.ppm or .png)
+       aoc2018 react <in> [<out>]        react the polymer in <in> (- for stdin), writing it to <out>
+       aoc2018 check <day> <file>        report problems in a day's input file
+       aoc2018 stats <day> [<file>]      summarise a day's input, or <file> instead";
 
-    do_problem(problem02::part1);
-    do_problem(problem02::part2);
+const TASKS: &[Task] = &[
+    Task { day: 1, part: 1, run: problem01::part1 },
+    Task { day: 1, part: 2, run: problem01::part2 },
+    Task { day: 2, part: 1, run: problem02::part1 },
+    Task { day: 2, part: 2, run: problem02::part2 },
+    Task { day: 3, part: 1, run: problem03::part1 },
+    Task { day: 3, part: 2, run: problem03::part2 },
+    Task { day: 4, part: 1, run: problem04::part1 },
+    Task { day: 4, part: 2, run: problem04::part2 },
+    Task { day: 5, part: 1, run: problem05::part1 },
+    Task { day: 5, part: 2, run: problem05::part2 },
+    Task { day: 6, part: 1, run: problem06::part1 },
+    Task { day: 6, part: 2, run: problem06::part2 },
+];
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
 
-    do_problem(problem03::part1);
-    do_problem(problem03::part2);
+    match args.first().map(String::as_str) {
+        None => run_all(),
+        Some("run") => run(&args[1..]),
+        Some("task") => task(&args[1..]),
+        Some("render") => render(&args[1..]),
+        Some("react") => react(&args[1..]),
+        Some("check") => check(&args[1..]),
+        Some("stats") => stats(&args[1..]),
+        Some(_) => exit_with_usage(),
+    }
+}
 
-    do_problem(problem04::part1);
-    do_problem(problem04::part2);
+fn run_all() {
+    let mut failed = false;
 
-    do_problem(problem05::part1);
-    do_problem(problem05::part2);
+    for task in TASKS {
+        failed |= report(&runner::run_task(task));
+    }
 
-    do_problem(problem06::part1);
-    do_problem(problem06::part2);
+    if failed {
+        process::exit(1)
+    }
 }
 
-fn time_it(f: fn() -> ()) {
-    use std::time::Instant;
+fn run(args: &[String]) {
+    let mut jobs = None;
+    let mut isolate = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--jobs" => {
+                jobs = match args.next().and_then(|n| n.parse().ok()) {
+                    Some(n) if n > 0 => Some(n),
+                    _ => exit_with_usage(),
+                }
+            }
+            "--isolate" => isolate = true,
+            _ => exit_with_usage(),
+        }
+    }
+
+    if isolate && jobs.is_some() {
+        eprintln!("--isolate runs one task at a time, it can't be combined with --jobs");
+        exit_with_usage()
+    }
 
     let now = Instant::now();
+    let mut failed = false;
 
-    f();
+    if isolate {
+        for task in TASKS {
+            failed |= report(&run_isolated(task));
+        }
+    } else {
+        let jobs = jobs.unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()));
+        runner::run_parallel(TASKS, jobs, |_, outcome| failed |= report(outcome));
+    }
 
-    let elapsed = now.elapsed();
+    println!("{: >20.04}ms total", runner::millis(now.elapsed()));
+
+    if failed {
+        process::exit(1)
+    }
+}
+
+/// Runs a single task in a fresh child process, one at a time, so nothing else competes with it.
+/// The child times itself so process start up is not counted.
+fn run_isolated(task: &Task) -> Outcome {
+    let failed = |why: String| Outcome {
+        output: format!("Problem {}, Part {}: {}", task.day, task.part, why),
+        elapsed: Duration::from_secs(0),
+        failed: true,
+    };
+
+    let exe = match env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => return failed(format!("could not find executable ({})", e)),
+    };
+
+    let out = match Command::new(exe).args(["task", &task.day.to_string(), &task.part.to_string()]).output() {
+        Ok(out) => out,
+        Err(e) => return failed(format!("failed to start ({})", e)),
+    };
+
+    // a child that caught its task panicking still reports the panic and its timing
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    match stdout.trim_end().rsplit_once('\n') {
+        Some((output, nanos)) => Outcome {
+            output: output.to_string(),
+            elapsed: Duration::from_nanos(nanos.parse().unwrap_or(0)),
+            failed: !out.status.success(),
+        },
+        None if !out.status.success() => failed(format!("failed ({})", out.status)),
+        None => failed("no timing reported".to_string()),
+    }
+}
+
+/// The child side of `run --isolate`: runs one task and prints its answer and elapsed nanoseconds
+fn task(args: &[String]) {
+    let found = match args {
+        [day, part] => TASKS.iter().find(|t| t.day.to_string() == *day && t.part.to_string() == *part),
+        _ => exit_with_usage(),
+    };
+
+    match found {
+        Some(task) => {
+            let outcome = runner::run_task(task);
+            println!("{}", outcome.output);
+            println!("{}", outcome.elapsed.as_nanos());
+
+            if outcome.failed {
+                process::exit(1)
+            }
+        }
+        None => {
+            eprintln!("no such task");
+            process::exit(1)
+        }
+    }
+}
 
-    let nanos = elapsed.as_secs() * 1_000_000_000u64 + elapsed.subsec_nanos() as u64;
-    let millis = nanos as f64 / 1_000_000f64;
+fn render(args: &[String]) {
+    let (day, out) = match args {
+        [day, out] => (day.as_str(), Path::new(out)),
+        _ => exit_with_usage(),
+    };
 
-    println!("{: >20.04}ms", millis);
+    let result = match day {
+        "3" => problem03::render(out),
+        "6" => problem06::render(out),
+        _ => {
+            eprintln!("no renderer for day {}", day);
+            process::exit(1)
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("failed to write {}: {}", out.display(), e);
+        process::exit(1)
+    }
+}
+
+fn react(args: &[String]) {
+    let (input, output) = match args {
+        [input] => (input, None),
+        [input, output] => (input, Some(Path::new(output))),
+        _ => exit_with_usage(),
+    };
+
+    let stdin = io::stdin();
+    let reader: Box<dyn BufRead> = if input == "-" {
+        Box::new(stdin.lock())
+    } else {
+        match File::open(input) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(e) => {
+                eprintln!("failed to read {}: {}", input, e);
+                process::exit(1)
+            }
+        }
+    };
+
+    let result = match output {
+        None => problem05::react_stream(reader),
+        Some(out) => File::create(out)
+            .and_then(|file| problem05::react_stream_into(reader, &mut BufWriter::new(file))),
+    };
+
+    match result {
+        Ok(len) => println!("{}", len),
+        Err(e) => {
+            eprintln!("failed to react {}: {}", input, e);
+            process::exit(1)
+        }
+    }
+}
+
+fn check(args: &[String]) {
+    let (day, file) = match args {
+        [day, file] => (day.as_str(), file),
+        _ => exit_with_usage(),
+    };
+
+    let input = read_or_exit(file);
+
+    let issues: Vec<String> = match day {
+        "4" => problem04::check_log(&input).iter().map(|issue| issue.to_string()).collect(),
+        _ => {
+            eprintln!("no checker for day {}", day);
+            process::exit(1)
+        }
+    };
+
+    if issues.is_empty() {
+        println!("{}: ok", file);
+    } else {
+        for issue in &issues {
+            println!("{}: {}", file, issue);
+        }
+        process::exit(1)
+    }
+}
+
+fn stats(args: &[String]) {
+    let (day, input) = match args {
+        [day] => (day.as_str(), None),
+        [day, file] => (day.as_str(), Some(read_or_exit(file))),
+        _ => exit_with_usage(),
+    };
+
+    let summary = match day {
+        "1" => problem01::stats(input.as_deref()),
+        "3" => Ok(problem03::stats(input.as_deref())),
+        "4" => problem04::stats(input.as_deref()),
+        "5" => Ok(problem05::stats(input.as_deref())),
+        "6" => problem06::stats(input.as_deref()),
+        _ => {
+            eprintln!("no stats for day {}", day);
+            process::exit(1)
+        }
+    };
+
+    match summary {
+        Ok(summary) => print!("{}", summary),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1)
+        }
+    }
+}
+
+fn read_or_exit(file: &str) -> String {
+    match fs::read_to_string(file) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("failed to read {}: {}", file, e);
+            process::exit(1)
+        }
+    }
+}
 
+fn exit_with_usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2)
 }
 
-fn do_problem(f: fn() -> ()) {
-    time_it(f)
+/// Print an outcome and its timing, returning whether the task failed
+fn report(outcome: &Outcome) -> bool {
+    println!("{}", outcome.output);
+    println!("{: >20.04}ms", runner::millis(outcome.elapsed));
+    outcome.failed
 }
\ No newline at end of file
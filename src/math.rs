@@ -95,4 +95,83 @@ impl SubAbs for f64 {
     fn sub_abs(self, other: Self) -> Self {
         (self - other).abs()
     }
-}
\ No newline at end of file
+}
+
+/// Signed integers mapped onto the unsigned integer of the same width so that small magnitudes of
+/// either sign stay small: 0 encodes to 0, -1 to 1, 1 to 2, -2 to 3, 2 to 4, etc
+///
+/// https://en.wikipedia.org/wiki/Variable-length_quantity#Zigzag_encoding
+#[allow(dead_code)]
+pub trait ZigZag: Sized {
+    type Unsigned;
+
+    /// Use the least significant bit for sign
+    fn zig_zag_encode(self) -> Self::Unsigned;
+
+    fn zig_zag_decode(n: Self::Unsigned) -> Self;
+}
+
+macro_rules! zig_zag {
+    ($($signed:ty => $unsigned:ty),*) => {
+        $(
+            impl ZigZag for $signed {
+                type Unsigned = $unsigned;
+
+                fn zig_zag_encode(self) -> $unsigned {
+                    ((self << 1) ^ (self >> (<$signed>::BITS - 1))) as $unsigned
+                }
+
+                fn zig_zag_decode(n: $unsigned) -> $signed {
+                    ((n >> 1) as $signed) ^ -((n & 1) as $signed)
+                }
+            }
+        )*
+    };
+}
+
+zig_zag!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zig_zag_small_values() {
+        assert_eq!(0u32, 0i32.zig_zag_encode());
+        assert_eq!(1u32, (-1i32).zig_zag_encode());
+        assert_eq!(2u32, 1i32.zig_zag_encode());
+        assert_eq!(3u32, (-2i32).zig_zag_encode());
+        assert_eq!(4u32, 2i32.zig_zag_encode());
+    }
+
+    #[test]
+    fn zig_zag_boundaries() {
+        assert_eq!(254u8, i8::MAX.zig_zag_encode());
+        assert_eq!(255u8, i8::MIN.zig_zag_encode());
+        assert_eq!(u16::MAX - 1, i16::MAX.zig_zag_encode());
+        assert_eq!(u16::MAX, i16::MIN.zig_zag_encode());
+        assert_eq!(4294967294u32, i32::MAX.zig_zag_encode());
+        assert_eq!(4294967295u32, i32::MIN.zig_zag_encode());
+        assert_eq!(u64::MAX - 1, i64::MAX.zig_zag_encode());
+        assert_eq!(u64::MAX, i64::MIN.zig_zag_encode());
+        assert_eq!(u128::MAX - 1, i128::MAX.zig_zag_encode());
+        assert_eq!(u128::MAX, i128::MIN.zig_zag_encode());
+        assert_eq!(usize::MAX, isize::MIN.zig_zag_encode());
+    }
+
+    #[test]
+    fn zig_zag_round_trips() {
+        for n in i8::MIN..=i8::MAX {
+            assert_eq!(n, i8::zig_zag_decode(n.zig_zag_encode()));
+        }
+        for &n in &[0, 1, -1, i64::MAX, i64::MIN, i64::MAX - 1, i64::MIN + 1] {
+            assert_eq!(n, i64::zig_zag_decode(n.zig_zag_encode()));
+        }
+        for &n in &[0, -1, i128::MAX, i128::MIN] {
+            assert_eq!(n, i128::zig_zag_decode(n.zig_zag_encode()));
+        }
+        for n in 0..=u16::MAX {
+            assert_eq!(n, i16::zig_zag_decode(n).zig_zag_encode());
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Splitting embarrassingly parallel work over std threads
+
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+/// The number of threads to split work over
+pub fn threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Split `0..len` into at most [chunks] contiguous, non empty ranges of near equal size
+pub fn split(len: usize, chunks: usize) -> Vec<Range<usize>> {
+    let chunks = chunks.max(1).min(len);
+    if chunks == 0 {
+        return Vec::new();
+    }
+
+    let (size, rest) = (len / chunks, len % chunks);
+    let mut start = 0;
+
+    (0..chunks)
+        .map(|i| {
+            let end = start + size + if i < rest { 1 } else { 0 };
+            let range = start..end;
+            start = end;
+            range
+        })
+        .collect()
+}
+
+/// Run [f] over chunks of `0..len` on separate threads. The results come back in chunk order
+/// no matter which thread finishes first, so merging them is deterministic
+pub fn map_ranges<R, F>(len: usize, f: F) -> Vec<R>
+    where R: Send,
+          F: Fn(Range<usize>) -> R + Sync
+{
+    map_chunks(len, threads(), f)
+}
+
+/// [map_ranges] with an explicit number of chunks. The chunks are shared out over at most
+/// [threads] threads, each taking the next unclaimed chunk when it finishes one
+pub fn map_chunks<R, F>(len: usize, chunks: usize, f: F) -> Vec<R>
+    where R: Send,
+          F: Fn(Range<usize>) -> R + Sync
+{
+    let ranges = split(len, chunks);
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..threads().min(ranges.len()) {
+            let (sender, next, ranges, f) = (sender.clone(), &next, &ranges, &f);
+            scope.spawn(move || {
+                while let Some(range) = ranges.get(next.fetch_add(1, Ordering::SeqCst)) {
+                    if sender.send((range.start, f(range.clone()))).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+    drop(sender);
+
+    let mut results: Vec<(usize, R)> = receiver.into_iter().collect();
+    results.sort_by_key(|&(start, _)| start);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_covers_everything_once() {
+        assert_eq!(vec![0..4, 4..7, 7..10], split(10, 3));
+        assert_eq!(vec![0..1, 1..2], split(2, 8));
+        assert_eq!(vec![0..5], split(5, 0));
+        assert!(split(0, 4).is_empty());
+    }
+
+    #[test]
+    fn results_are_in_chunk_order() {
+        let sums = map_ranges(1000, |range| range.start);
+        let mut sorted = sums.clone();
+        sorted.sort();
+
+        assert_eq!(sorted, sums);
+        assert_eq!(0, sums[0]);
+        assert_eq!(499500usize, map_ranges(1000, |range| range.sum::<usize>()).iter().sum());
+        assert_eq!(vec![0, 250, 500, 750], map_chunks(1000, 4, |range| range.start));
+    }
+
+    #[test]
+    fn chunks_share_at_most_one_thread_per_core() {
+        use std::collections::HashSet;
+        use std::sync::Mutex;
+
+        let seen = Mutex::new(HashSet::new());
+        let starts = map_chunks(1000, 64, |range| {
+            seen.lock().unwrap().insert(thread::current().id());
+            range.start
+        });
+
+        assert_eq!(64, starts.len());
+        assert_eq!(split(1000, 64).iter().map(|range| range.start).collect::<Vec<_>>(), starts);
+        assert!(seen.lock().unwrap().len() <= threads());
+    }
+}
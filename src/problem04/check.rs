@@ -0,0 +1,178 @@
+//! Finding structural problems in a guard log before trusting its answers
+
+use ::std::fmt;
+use super::log::{EventKind, GuardEvent, Timestamp};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// The line is not an event at all
+    Unparseable(&'static str),
+    /// The same minute was logged on an earlier line
+    DuplicateTimestamp { previous: usize },
+    /// Falls asleep while already asleep since an earlier line
+    DoubleSleep { since: usize },
+    /// Wakes up without having fallen asleep
+    WakeWithoutSleep,
+    /// Falls asleep before any guard has begun a shift
+    SleepBeforeShift,
+    /// The shift of [guard] ended (or the log did) while they were asleep. Reported on the line
+    /// they fell asleep
+    ShiftEndsAsleep { guard: u32 },
+}
+
+/// A problem and the 1 based line it was found on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub line: usize,
+    pub problem: Problem,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+
+        match self.problem {
+            Problem::Unparseable(reason) => write!(f, "{}", reason),
+            Problem::DuplicateTimestamp { previous } => write!(f, "same timestamp as line {}", previous),
+            Problem::DoubleSleep { since } => write!(f, "falls asleep again, already asleep since line {}", since),
+            Problem::WakeWithoutSleep => write!(f, "wakes up without falling asleep"),
+            Problem::SleepBeforeShift => write!(f, "falls asleep before any shift begins"),
+            Problem::ShiftEndsAsleep { guard } => write!(f, "guard #{} falls asleep and never wakes up", guard),
+        }
+    }
+}
+
+/// Every problem in the log, ordered by line. Lines that fail to parse are reported and skipped,
+/// the rest are checked in chronological order
+pub fn check_log(input: &str) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut events: Vec<(usize, GuardEvent)> = Vec::new();
+
+    for (i, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match GuardEvent::parse(line) {
+            Ok(event) => events.push((i + 1, event)),
+            Err(reason) => issues.push(Issue { line: i + 1, problem: Problem::Unparseable(reason) }),
+        }
+    }
+
+    events.sort_by_key(|&(_, event)| event.timestamp);
+
+    let mut previous: Option<(usize, Timestamp)> = None;
+    let mut guard: Option<u32> = None;
+    let mut asleep_since: Option<usize> = None;
+
+    for (line, event) in events {
+        let mut report = |problem| issues.push(Issue { line, problem });
+
+        if let Some((previous, timestamp)) = previous {
+            if timestamp == event.timestamp {
+                report(Problem::DuplicateTimestamp { previous });
+            }
+        }
+        previous = Some((line, event.timestamp));
+
+        match event.kind {
+            EventKind::BeginsShift(id) => {
+                if let (Some(since), Some(guard)) = (asleep_since, guard) {
+                    issues.push(Issue { line: since, problem: Problem::ShiftEndsAsleep { guard } });
+                }
+                guard = Some(id);
+                asleep_since = None;
+            }
+
+            EventKind::FallsAsleep => {
+                if guard.is_none() {
+                    report(Problem::SleepBeforeShift);
+                }
+                match asleep_since {
+                    Some(since) => report(Problem::DoubleSleep { since }),
+                    None => asleep_since = Some(line),
+                }
+            }
+
+            EventKind::WakesUp => {
+                if asleep_since.is_none() {
+                    report(Problem::WakeWithoutSleep);
+                }
+                asleep_since = None;
+            }
+        }
+    }
+
+    if let (Some(since), Some(guard)) = (asleep_since, guard) {
+        issues.push(Issue { line: since, problem: Problem::ShiftEndsAsleep { guard } });
+    }
+
+    issues.sort_by_key(|issue| issue.line);
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::input::INPUT;
+
+    fn problems(input: &str) -> Vec<(usize, Problem)> {
+        check_log(input).into_iter().map(|issue| (issue.line, issue.problem)).collect()
+    }
+
+    #[test]
+    fn input_is_well_formed() {
+        assert!(check_log(INPUT).is_empty());
+    }
+
+    #[test]
+    fn finds_every_kind_of_problem() {
+        let log = "\
+[1518-11-01 00:01] falls asleep
+[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:05] falls asleep
+[1518-11-01 00:25] wakes up
+[1518-11-01 00:30] wakes up
+[1518-11-01 23:58] Guard #99 begins shift
+[1518-11-02 00:40] falls asleep
+[1518-11-02 00:45] falls asleep
+[1518-11-02 00:50] wakes up
+[1518-11-02 00:50] wakes up
+[1518-11-03 00:05] Guard #10 begins shift
+[1518-11-03 00:24] falls asleep
+[1518-11-04 00:02] Guard #99 begins shift
+Guard #99 begins shift
+[1518-11-04 00:36] falls asleep
+";
+
+        assert_eq!(vec![
+            // line 1 is out of order, it happens after the shift on line 2 begins
+            (3, Problem::DoubleSleep { since: 1 }),
+            (5, Problem::WakeWithoutSleep),
+            (8, Problem::DoubleSleep { since: 7 }),
+            (10, Problem::DuplicateTimestamp { previous: 9 }),
+            (10, Problem::WakeWithoutSleep),
+            (12, Problem::ShiftEndsAsleep { guard: 10 }),
+            (14, Problem::Unparseable("expected [ before the timestamp")),
+            (15, Problem::ShiftEndsAsleep { guard: 99 }),
+        ], problems(log));
+    }
+
+    #[test]
+    fn sleep_before_any_shift() {
+        let log = "\
+[1518-11-01 00:05] falls asleep
+[1518-11-01 00:25] wakes up
+";
+        assert_eq!(vec![(1, Problem::SleepBeforeShift)], problems(log));
+    }
+
+    #[test]
+    fn display() {
+        let issue = Issue { line: 3, problem: Problem::DoubleSleep { since: 1 } };
+        assert_eq!("line 3: falls asleep again, already asleep since line 1", issue.to_string());
+
+        let issue = Issue { line: 7, problem: Problem::ShiftEndsAsleep { guard: 10 } };
+        assert_eq!("line 7: guard #10 falls asleep and never wakes up", issue.to_string());
+    }
+}
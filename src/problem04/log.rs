@@ -0,0 +1,292 @@
+//! Parsing the guard log into typed events
+
+use ::std::fmt;
+
+/// A minute on the calendar. Fields are ordered so that timestamps sort chronologically
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+}
+
+impl Timestamp {
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8) -> Result<Timestamp, &'static str> {
+        if !(1..=12).contains(&month) {
+            return Err("month out of range");
+        }
+        if day < 1 || day > days_in_month(year, month) {
+            return Err("day out of range");
+        }
+        if hour > 23 {
+            return Err("hour out of range");
+        }
+        if minute > 59 {
+            return Err("minute out of range");
+        }
+
+        Ok(Timestamp { year, month, day, hour, minute })
+    }
+
+    /// Parse `YYYY-MM-DD HH:MM`, allowing any whitespace between the date and the time
+    pub fn parse(text: &str) -> Result<Timestamp, &'static str> {
+        let mut parts = text.split_whitespace();
+        let (date, time) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(date), Some(time), None) => (date, time),
+            _ => return Err("expected a date and a time"),
+        };
+
+        let date: Vec<&str> = date.split('-').collect();
+        let time: Vec<&str> = time.split(':').collect();
+
+        match (&date[..], &time[..]) {
+            ([year, month, day], [hour, minute]) => Timestamp::new(
+                number(year)?,
+                number(month)?,
+                number(day)?,
+                number(hour)?,
+                number(minute)?,
+            ),
+            _ => Err("expected YYYY-MM-DD HH:MM"),
+        }
+    }
+}
+
+/// A day on the calendar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl Date {
+    /// The following day
+    pub fn succ(&self) -> Date {
+        if self.day < days_in_month(self.year, self.month) {
+            Date { day: self.day + 1, ..*self }
+        } else if self.month < 12 {
+            Date { month: self.month + 1, day: 1, ..*self }
+        } else {
+            Date { year: self.year + 1, month: 1, day: 1 }
+        }
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl Timestamp {
+    pub fn date(&self) -> Date {
+        Date { year: self.year, month: self.month, day: self.day }
+    }
+
+    /// Minutes since midnight, `0..1440`
+    pub fn minute_of_day(&self) -> usize {
+        self.hour as usize * 60 + self.minute as usize
+    }
+
+    /// The following minute, rolling over into the next hour, day, month or year
+    pub fn succ(&self) -> Timestamp {
+        if self.minute < 59 {
+            Timestamp { minute: self.minute + 1, ..*self }
+        } else if self.hour < 23 {
+            Timestamp { hour: self.hour + 1, minute: 0, ..*self }
+        } else {
+            let date = self.date().succ();
+            Timestamp { year: date.year, month: date.month, day: date.day, hour: 0, minute: 0 }
+        }
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02} {:02}:{:02}", self.year, self.month, self.day, self.hour, self.minute)
+    }
+}
+
+fn number<N: ::std::str::FromStr>(text: &str) -> Result<N, &'static str> {
+    if text.is_empty() || !text.bytes().all(|c| c.is_ascii_digit()) {
+        return Err("expected a number");
+    }
+    text.parse().map_err(|_| "number too large")
+}
+
+fn is_leap_year(year: u16) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    BeginsShift(u32),
+    FallsAsleep,
+    WakesUp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuardEvent {
+    pub timestamp: Timestamp,
+    pub kind: EventKind,
+}
+
+impl GuardEvent {
+    /// Parse a line like `[1518-11-01 00:00] Guard #10 begins shift`. Whitespace between words is
+    /// free and ids may be any width
+    pub fn parse(line: &str) -> Result<GuardEvent, &'static str> {
+        let line = line.trim();
+
+        if !line.starts_with('[') {
+            return Err("expected [ before the timestamp");
+        }
+        let close = line.find(']').ok_or("expected ] after the timestamp")?;
+
+        let timestamp = Timestamp::parse(&line[1..close])?;
+
+        let words: Vec<&str> = line[close + 1..].split_whitespace().collect();
+        let kind = match words[..] {
+            ["falls", "asleep"] => EventKind::FallsAsleep,
+            ["wakes", "up"] => EventKind::WakesUp,
+            ["Guard", id, "begins", "shift"] if id.starts_with('#') => EventKind::BeginsShift(number(&id[1..])?),
+            _ => return Err("unknown event"),
+        };
+
+        Ok(GuardEvent { timestamp, kind })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLogError {
+    /// 1 based
+    pub line: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ParseLogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+/// Parse every non blank line of [input], in chronological order. Events at the same minute keep
+/// the order they were logged in
+pub fn parse_log(input: &str) -> Result<Vec<GuardEvent>, ParseLogError> {
+    let mut events = parse_lines(input)?
+        .into_iter()
+        .map(|(_, event)| event)
+        .collect::<Vec<_>>();
+
+    events.sort_by_key(|event| event.timestamp);
+    Ok(events)
+}
+
+/// Parse every non blank line of [input] along with its 1 based line number, in logged order
+pub fn parse_lines(input: &str) -> Result<Vec<(usize, GuardEvent)>, ParseLogError> {
+    input.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            GuardEvent::parse(line)
+                .map(|event| (i + 1, event))
+                .map_err(|reason| ParseLogError { line: i + 1, reason })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(year: u16, month: u8, day: u8, hour: u8, minute: u8) -> Timestamp {
+        Timestamp { year, month, day, hour, minute }
+    }
+
+    #[test]
+    fn parse_events() {
+        assert_eq!(Ok(GuardEvent { timestamp: ts(1518, 11, 1, 23, 58), kind: EventKind::BeginsShift(99) }),
+                   GuardEvent::parse("[1518-11-01 23:58] Guard #99 begins shift"));
+        assert_eq!(Ok(GuardEvent { timestamp: ts(1518, 11, 2, 0, 40), kind: EventKind::FallsAsleep }),
+                   GuardEvent::parse("[1518-11-02 00:40] falls asleep"));
+        assert_eq!(Ok(GuardEvent { timestamp: ts(1518, 11, 2, 0, 50), kind: EventKind::WakesUp }),
+                   GuardEvent::parse("[1518-11-02 00:50] wakes up"));
+    }
+
+    #[test]
+    fn parse_is_lenient_with_spacing_and_widths() {
+        assert_eq!(Ok(GuardEvent { timestamp: ts(1518, 3, 4, 0, 0), kind: EventKind::BeginsShift(1234567) }),
+                   GuardEvent::parse("  [1518-03-04   00:00]   Guard  #1234567   begins shift  "));
+        assert_eq!(Ok(GuardEvent { timestamp: ts(1518, 3, 4, 0, 1), kind: EventKind::WakesUp }),
+                   GuardEvent::parse("[ 1518-03-04 00:01 ]wakes\tup"));
+    }
+
+    #[test]
+    fn parse_rejects_bad_fields() {
+        assert_eq!(Err("month out of range"), Timestamp::parse("1518-13-01 00:00"));
+        assert_eq!(Err("day out of range"), Timestamp::parse("1518-02-29 00:00"));
+        assert_eq!(Ok(ts(1520, 2, 29, 0, 0)), Timestamp::parse("1520-02-29 00:00"));
+        assert_eq!(Err("day out of range"), Timestamp::parse("1518-04-31 00:00"));
+        assert_eq!(Err("hour out of range"), Timestamp::parse("1518-04-30 24:00"));
+        assert_eq!(Err("minute out of range"), Timestamp::parse("1518-04-30 00:60"));
+        assert_eq!(Err("expected a number"), Timestamp::parse("1518-04-+3 00:00"));
+        assert_eq!(Err("expected YYYY-MM-DD HH:MM"), Timestamp::parse("1518-04 00:00"));
+
+        assert_eq!(Err("unknown event"), GuardEvent::parse("[1518-04-30 00:00] Guard 10 begins shift"));
+        assert_eq!(Err("unknown event"), GuardEvent::parse("[1518-04-30 00:00] sleeps"));
+        assert_eq!(Err("expected a number"), GuardEvent::parse("[1518-04-30 00:00] Guard #x begins shift"));
+        assert_eq!(Err("expected ] after the timestamp"), GuardEvent::parse("[1518-04-30 00:00 wakes up"));
+    }
+
+    #[test]
+    fn log_is_sorted_by_timestamp() {
+        let log = "\
+[1518-11-02 00:40] falls asleep
+[1518-11-01 23:58] Guard #99 begins shift
+
+[1518-11-02 00:50] wakes up
+";
+        let kinds: Vec<EventKind> = parse_log(log).unwrap().iter().map(|e| e.kind).collect();
+        assert_eq!(vec![EventKind::BeginsShift(99), EventKind::FallsAsleep, EventKind::WakesUp], kinds);
+
+        assert_eq!(Err(ParseLogError { line: 3, reason: "expected [ before the timestamp" }),
+                   parse_log("[1518-11-02 00:40] falls asleep\n\nnonsense"));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!("1518-03-04 00:05", ts(1518, 3, 4, 0, 5).to_string());
+        assert_eq!("1518-03-04", ts(1518, 3, 4, 0, 5).date().to_string());
+    }
+
+    #[test]
+    fn next_minute() {
+        assert_eq!(ts(1518, 3, 4, 0, 6), ts(1518, 3, 4, 0, 5).succ());
+        assert_eq!(ts(1518, 3, 4, 1, 0), ts(1518, 3, 4, 0, 59).succ());
+        assert_eq!(ts(1518, 3, 5, 0, 0), ts(1518, 3, 4, 23, 59).succ());
+        assert_eq!(ts(1519, 1, 1, 0, 0), ts(1518, 12, 31, 23, 59).succ());
+        assert_eq!(1439, ts(1518, 3, 4, 23, 59).minute_of_day());
+    }
+
+    #[test]
+    fn next_day() {
+        let date = |year, month, day| Date { year, month, day };
+
+        assert_eq!(date(1518, 3, 5), date(1518, 3, 4).succ());
+        assert_eq!(date(1518, 3, 1), date(1518, 2, 28).succ());
+        assert_eq!(date(1520, 2, 29), date(1520, 2, 28).succ());
+        assert_eq!(date(1518, 5, 1), date(1518, 4, 30).succ());
+        assert_eq!(date(1519, 1, 1), date(1518, 12, 31).succ());
+    }
+}
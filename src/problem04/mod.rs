@@ -1,5 +1,12 @@
 mod solution;
 mod input;
+#[allow(dead_code)]
+mod log;
+mod check;
+#[allow(dead_code)]
+mod schedule;
 
 pub use self::solution::part1;
-pub use self::solution::part2;
\ No newline at end of file
+pub use self::solution::part2;
+pub use self::solution::stats;
+pub use self::check::check_log;
\ No newline at end of file
@@ -0,0 +1,478 @@
+//! The guard log replayed once into who slept when
+
+use ::std::collections::BTreeMap;
+use ::std::iter::successors;
+use ::bitvec::BitVec64;
+use super::log::{Date, EventKind, GuardEvent, Timestamp};
+
+const MINUTES_PER_DAY: usize = 24 * 60;
+
+/// One guard's shift. Bit `m` of [asleep] is set if they were asleep during minute `00:m` of
+/// [date], the midnight hour the puzzle cares about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Night {
+    pub date: Date,
+    pub guard: u32,
+    pub asleep: BitVec64,
+}
+
+/// A stretch of sleep from [start] up to but not including [end], at any time of day
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Nap {
+    pub guard: u32,
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+impl Nap {
+    /// Every minute asleep, in order
+    pub fn minutes(&self) -> impl Iterator<Item = Timestamp> {
+        let end = self.end;
+        successors(Some(self.start), |t| Some(t.succ())).take_while(move |&t| t < end)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SleepSchedule {
+    nights: Vec<Night>,
+    naps: Vec<Nap>,
+    /// For each guard, how many nights they were asleep during each minute of the midnight hour
+    minutes: BTreeMap<u32, [u32; 60]>,
+    /// For each guard, how many times they were asleep during each minute of the day
+    day_minutes: BTreeMap<u32, Vec<u32>>,
+}
+
+impl SleepSchedule {
+    /// Replay [events], which must be in chronological order. Sleep logged before any shift begins
+    /// is ignored, and a guard still asleep when their shift ends is counted as waking at the end
+    /// of the hour they fell asleep in
+    pub fn from_events(events: &[GuardEvent]) -> SleepSchedule {
+        let mut nights: Vec<Night> = Vec::new();
+        let mut naps: Vec<Nap> = Vec::new();
+        let mut asleep_since: Option<Timestamp> = None;
+
+        let mut nap = |nights: &mut Vec<Night>, start: Timestamp, end: Timestamp| {
+            if let Some(night) = nights.last_mut() {
+                let nap = Nap { guard: night.guard, start, end };
+                for t in nap.minutes() {
+                    if t.hour == 0 && t.date() == night.date {
+                        night.asleep.set(t.minute as usize);
+                    }
+                }
+                naps.push(nap);
+            }
+        };
+
+        let end_of_hour = |t: Timestamp| Timestamp { minute: 59, ..t }.succ();
+
+        for event in events {
+            match event.kind {
+                EventKind::BeginsShift(guard) => {
+                    if let Some(since) = asleep_since.take() {
+                        nap(&mut nights, since, end_of_hour(since));
+                    }
+
+                    // shifts that begin before midnight cover the next day's midnight hour
+                    let date = event.timestamp.date();
+                    let date = if event.timestamp.hour >= 12 { date.succ() } else { date };
+                    nights.push(Night { date, guard, asleep: BitVec64::new() });
+                }
+
+                EventKind::FallsAsleep => {
+                    if asleep_since.is_none() {
+                        asleep_since = Some(event.timestamp);
+                    }
+                }
+
+                EventKind::WakesUp => {
+                    if let Some(since) = asleep_since.take() {
+                        nap(&mut nights, since, event.timestamp);
+                    }
+                }
+            }
+        }
+        if let Some(since) = asleep_since {
+            nap(&mut nights, since, end_of_hour(since));
+        }
+
+        let mut minutes: BTreeMap<u32, [u32; 60]> = BTreeMap::new();
+        for night in &nights {
+            let counts = minutes.entry(night.guard).or_insert([0; 60]);
+            for m in night.asleep.iter() {
+                counts[m] += 1;
+            }
+        }
+
+        let mut day_minutes: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        for night in &nights {
+            day_minutes.entry(night.guard).or_insert_with(|| vec![0; MINUTES_PER_DAY]);
+        }
+        for nap in &naps {
+            let counts = day_minutes.get_mut(&nap.guard).unwrap();
+            for t in nap.minutes() {
+                counts[t.minute_of_day()] += 1;
+            }
+        }
+
+        SleepSchedule { nights, naps, minutes, day_minutes }
+    }
+
+    pub fn nights(&self) -> &[Night] {
+        &self.nights
+    }
+
+    pub fn naps(&self) -> &[Nap] {
+        &self.naps
+    }
+
+    /// Every guard that worked a shift, in id order
+    pub fn guards(&self) -> impl Iterator<Item = u32> + '_ {
+        self.minutes.keys().cloned()
+    }
+
+    /// For each minute of the hour, the number of nights [guard] was asleep during it
+    pub fn minute_counts(&self, guard: u32) -> [u32; 60] {
+        self.minutes.get(&guard).cloned().unwrap_or([0; 60])
+    }
+
+    /// For each minute of the day, the number of times [guard] was asleep during it
+    pub fn minute_of_day_counts(&self, guard: u32) -> Vec<u32> {
+        self.day_minutes.get(&guard).cloned().unwrap_or_else(|| vec![0; MINUTES_PER_DAY])
+    }
+
+    /// For each hour of the day, the total minutes [guard] was asleep during it
+    pub fn hour_counts(&self, guard: u32) -> [u32; 24] {
+        let mut hours = [0; 24];
+        for (minute, count) in self.minute_of_day_counts(guard).iter().enumerate() {
+            hours[minute / 60] += count;
+        }
+        hours
+    }
+
+    /// The total minutes [guard] was asleep over all their shifts, at any time of day
+    pub fn total_asleep(&self, guard: u32) -> u32 {
+        self.day_minutes.get(&guard).map_or(0, |counts| counts.iter().sum())
+    }
+
+    /// The minutes [guard] was asleep during the midnight hour, the only hour the puzzle counts
+    pub fn midnight_asleep(&self, guard: u32) -> u32 {
+        self.minute_counts(guard).iter().sum()
+    }
+
+    /// The guard with the most minutes asleep during the midnight hour
+    pub fn sleepiest_guard(&self) -> Option<u32> {
+        self.guards().max_by_key(|&guard| self.midnight_asleep(guard))
+    }
+
+    /// The minute [guard] was most often asleep and how many nights they were. None if they never
+    /// slept
+    pub fn most_frequent_minute(&self, guard: u32) -> Option<(usize, u32)> {
+        self.minute_counts(guard).iter()
+            .cloned()
+            .enumerate()
+            .filter(|&(_, count)| count > 0)
+            .max_by_key(|&(_, count)| count)
+    }
+
+    /// Of every guard, the one most frequently asleep on the same minute, with that minute
+    pub fn most_frequent_sleeper(&self) -> Option<(u32, usize)> {
+        self.guards()
+            .filter_map(|guard| self.most_frequent_minute(guard).map(|(minute, count)| (guard, minute, count)))
+            .max_by_key(|&(_, _, count)| count)
+            .map(|(guard, minute, _)| (guard, minute))
+    }
+
+    /// How many shifts [guard] worked
+    pub fn shift_count(&self, guard: u32) -> usize {
+        self.nights.iter().filter(|night| night.guard == guard).count()
+    }
+
+    /// The minutes [guard] was asleep per shift, on average. None if they never worked
+    pub fn average_asleep(&self, guard: u32) -> Option<f64> {
+        match self.shift_count(guard) {
+            0 => None,
+            shifts => Some(self.total_asleep(guard) as f64 / shifts as f64),
+        }
+    }
+
+    /// For each number of shifts, how many guards worked that many
+    pub fn shift_count_distribution(&self) -> BTreeMap<usize, usize> {
+        let mut distribution = BTreeMap::new();
+        for guard in self.guards() {
+            *distribution.entry(self.shift_count(guard)).or_insert(0) += 1;
+        }
+        distribution
+    }
+
+    /// The guard asleep the least per shift on average. Ties go to the lowest id
+    pub fn most_reliable_guard(&self) -> Option<u32> {
+        self.guards()
+            .map(|guard| (guard, self.total_asleep(guard) as u64, self.shift_count(guard) as u64))
+            .min_by(|&(_, a_asleep, a_shifts), &(_, b_asleep, b_shifts)| {
+                (a_asleep * b_shifts).cmp(&(b_asleep * a_shifts))
+            })
+            .map(|(guard, _, _)| guard)
+    }
+
+    /// The guard asleep the most per shift on average. Ties go to the lowest id
+    pub fn least_reliable_guard(&self) -> Option<u32> {
+        self.guards()
+            .map(|guard| (guard, self.total_asleep(guard) as u64, self.shift_count(guard) as u64))
+            .min_by(|&(_, a_asleep, a_shifts), &(_, b_asleep, b_shifts)| {
+                (b_asleep * a_shifts).cmp(&(a_asleep * b_shifts))
+            })
+            .map(|(guard, _, _)| guard)
+    }
+
+    /// The guards asleep during minute `00:`[minute] on [date]
+    pub fn asleep_at(&self, date: Date, minute: usize) -> Vec<u32> {
+        self.nights.iter()
+            .filter(|night| night.date == date && minute < 60 && night.asleep.get(minute))
+            .map(|night| night.guard)
+            .collect()
+    }
+
+    /// The guards asleep during the minute starting at [time]
+    pub fn asleep_during(&self, time: Timestamp) -> Vec<u32> {
+        self.naps.iter()
+            .filter(|nap| nap.start <= time && time < nap.end)
+            .map(|nap| nap.guard)
+            .collect()
+    }
+
+    /// Every night as a row of `.` awake and `#` asleep minutes, laid out like the puzzle text
+    ///
+    /// ```text
+    /// Date   ID   Minute
+    ///             000000000011111111112222222222333333333344444444445555555555
+    ///             012345678901234567890123456789012345678901234567890123456789
+    /// 11-01  #10  .....####################.....#########################.....
+    /// ```
+    pub fn timeline(&self) -> String {
+        let id_width = self.guards().map(|guard| guard.to_string().len() + 1).max().unwrap_or(2);
+        let indent = " ".repeat(7 + id_width + 2);
+
+        let tens: String = (0..60).map(|m| (b'0' + m / 10) as char).collect();
+        let ones: String = (0..60).map(|m| (b'0' + m % 10) as char).collect();
+
+        let mut out = format!("{:<7}{:<w$}  Minute\n", "Date", "ID", w = id_width);
+        out += &format!("{}{}\n{}{}\n", indent, tens, indent, ones);
+
+        for night in &self.nights {
+            let minutes: String = (0..60).map(|m| if night.asleep.get(m) { '#' } else { '.' }).collect();
+            let id = format!("#{}", night.guard);
+            out += &format!("{:02}-{:02}  {:<w$}  {}\n", night.date.month, night.date.day, id, minutes, w = id_width);
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::log::parse_log;
+
+    const SAMPLE_INPUT: &str = "\
+[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:05] falls asleep
+[1518-11-01 00:25] wakes up
+[1518-11-01 00:30] falls asleep
+[1518-11-01 00:55] wakes up
+[1518-11-01 23:58] Guard #99 begins shift
+[1518-11-02 00:40] falls asleep
+[1518-11-02 00:50] wakes up
+[1518-11-03 00:05] Guard #10 begins shift
+[1518-11-03 00:24] falls asleep
+[1518-11-03 00:29] wakes up
+[1518-11-04 00:02] Guard #99 begins shift
+[1518-11-04 00:36] falls asleep
+[1518-11-04 00:46] wakes up
+[1518-11-05 00:03] Guard #99 begins shift
+[1518-11-05 00:45] falls asleep
+[1518-11-05 00:55] wakes up";
+
+    fn sample() -> SleepSchedule {
+        SleepSchedule::from_events(&parse_log(SAMPLE_INPUT).unwrap())
+    }
+
+    #[test]
+    fn nights() {
+        let schedule = sample();
+        let nights = schedule.nights();
+
+        assert_eq!(5, nights.len());
+        assert_eq!(Date { year: 1518, month: 11, day: 2 }, nights[1].date);
+        assert_eq!(99, nights[1].guard);
+        assert_eq!((40..50).collect::<Vec<usize>>(), nights[1].asleep.iter().collect::<Vec<usize>>());
+        assert_eq!(45, nights[0].asleep.len());
+    }
+
+    #[test]
+    fn queries() {
+        let schedule = sample();
+
+        assert_eq!(vec![10, 99], schedule.guards().collect::<Vec<u32>>());
+        assert_eq!(50, schedule.total_asleep(10));
+        assert_eq!(30, schedule.total_asleep(99));
+        assert_eq!(0, schedule.total_asleep(7));
+
+        assert_eq!(Some(10), schedule.sleepiest_guard());
+        assert_eq!(Some((24, 2)), schedule.most_frequent_minute(10));
+        assert_eq!(Some((45, 3)), schedule.most_frequent_minute(99));
+        assert_eq!(None, schedule.most_frequent_minute(7));
+        assert_eq!(Some((99, 45)), schedule.most_frequent_sleeper());
+    }
+
+    #[test]
+    fn analytics() {
+        let schedule = sample();
+
+        assert_eq!(2, schedule.shift_count(10));
+        assert_eq!(3, schedule.shift_count(99));
+        assert_eq!(Some(25.0), schedule.average_asleep(10));
+        assert_eq!(Some(10.0), schedule.average_asleep(99));
+        assert_eq!(None, schedule.average_asleep(7));
+
+        let distribution: Vec<(usize, usize)> = schedule.shift_count_distribution().into_iter().collect();
+        assert_eq!(vec![(2, 1), (3, 1)], distribution);
+
+        assert_eq!(Some(99), schedule.most_reliable_guard());
+        assert_eq!(Some(10), schedule.least_reliable_guard());
+
+        let counts = schedule.minute_counts(99);
+        assert_eq!(3, counts[45]);
+        assert_eq!(0, counts[35]);
+        assert_eq!(1, counts[36]);
+    }
+
+    #[test]
+    fn reliability_ties_go_to_the_lowest_id() {
+        let log = "\
+[1518-11-01 00:00] Guard #20 begins shift
+[1518-11-02 00:00] Guard #10 begins shift";
+        let schedule = SleepSchedule::from_events(&parse_log(log).unwrap());
+
+        assert_eq!(Some(10), schedule.most_reliable_guard());
+        assert_eq!(Some(10), schedule.least_reliable_guard());
+        assert_eq!(None, SleepSchedule::from_events(&[]).most_reliable_guard());
+    }
+
+    #[test]
+    fn asleep_at() {
+        let schedule = sample();
+        let date = |day| Date { year: 1518, month: 11, day };
+
+        assert_eq!(vec![10], schedule.asleep_at(date(1), 5));
+        assert!(schedule.asleep_at(date(1), 25).is_empty());
+        assert_eq!(vec![99], schedule.asleep_at(date(2), 45));
+        assert!(schedule.asleep_at(date(6), 45).is_empty());
+        assert!(schedule.asleep_at(date(2), 60).is_empty());
+    }
+
+    #[test]
+    fn timeline_matches_the_puzzle() {
+        let expected = "\
+Date   ID   Minute
+            000000000011111111112222222222333333333344444444445555555555
+            012345678901234567890123456789012345678901234567890123456789
+11-01  #10  .....####################.....#########################.....
+11-02  #99  ........................................##########..........
+11-03  #10  ........................#####...............................
+11-04  #99  ....................................##########..............
+11-05  #99  .............................................##########.....
+";
+        assert_eq!(expected, sample().timeline());
+    }
+
+    #[test]
+    fn naps_outside_the_midnight_hour() {
+        let log = "\
+[1518-11-01 23:40] Guard #10 begins shift
+[1518-11-01 23:50] falls asleep
+[1518-11-02 00:10] wakes up
+[1518-11-02 03:00] falls asleep
+[1518-11-02 03:30] wakes up
+[1518-11-02 22:00] Guard #99 begins shift
+[1518-11-02 22:59] falls asleep
+[1518-11-02 23:01] wakes up";
+        let schedule = SleepSchedule::from_events(&parse_log(log).unwrap());
+        let ts = |day, hour, minute| Timestamp { year: 1518, month: 11, day, hour, minute };
+
+        assert_eq!(vec![
+            Nap { guard: 10, start: ts(1, 23, 50), end: ts(2, 0, 10) },
+            Nap { guard: 10, start: ts(2, 3, 0), end: ts(2, 3, 30) },
+            Nap { guard: 99, start: ts(2, 22, 59), end: ts(2, 23, 1) },
+        ], schedule.naps());
+
+        // only the midnight hour of the night's own date shows up in the puzzle's view
+        assert_eq!(10, schedule.nights()[0].asleep.len());
+        assert!(schedule.nights()[1].asleep.is_empty());
+        assert_eq!(Some((9, 1)), schedule.most_frequent_minute(10));
+
+        assert_eq!(50, schedule.total_asleep(10));
+        assert_eq!(2, schedule.total_asleep(99));
+        assert_eq!(10, schedule.midnight_asleep(10));
+        assert_eq!(0, schedule.midnight_asleep(99));
+        assert_eq!(Some(10), schedule.sleepiest_guard());
+
+        let hours = schedule.hour_counts(10);
+        assert_eq!((10, 10, 30), (hours[23], hours[0], hours[3]));
+        assert_eq!(50, hours.iter().sum::<u32>());
+        assert_eq!([1, 1], [schedule.hour_counts(99)[22], schedule.hour_counts(99)[23]]);
+
+        let day = schedule.minute_of_day_counts(10);
+        assert_eq!(MINUTES_PER_DAY, day.len());
+        assert_eq!((1, 1, 0), (day[23 * 60 + 59], day[9], day[10]));
+
+        assert_eq!(vec![10], schedule.asleep_during(ts(1, 23, 55)));
+        assert_eq!(vec![99], schedule.asleep_during(ts(2, 23, 0)));
+        assert!(schedule.asleep_during(ts(2, 23, 1)).is_empty());
+    }
+
+    #[test]
+    fn input_still_matches() {
+        use super::super::input::INPUT;
+
+        // every nap in the real log is inside the midnight hour
+        let schedule = SleepSchedule::from_events(&parse_log(INPUT).unwrap());
+        for guard in schedule.guards().collect::<Vec<u32>>() {
+            let hours = schedule.hour_counts(guard);
+            assert_eq!(schedule.total_asleep(guard), hours[0]);
+            assert_eq!(schedule.total_asleep(guard), schedule.midnight_asleep(guard));
+            assert_eq!(&schedule.minute_counts(guard)[..], &schedule.minute_of_day_counts(guard)[..60]);
+        }
+    }
+
+    #[test]
+    fn sleepiest_guard_counts_only_the_midnight_hour() {
+        let log = "\
+[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 03:00] falls asleep
+[1518-11-01 05:00] wakes up
+[1518-11-02 00:00] Guard #99 begins shift
+[1518-11-02 00:10] falls asleep
+[1518-11-02 00:20] wakes up";
+        let schedule = SleepSchedule::from_events(&parse_log(log).unwrap());
+
+        assert_eq!((120, 10), (schedule.total_asleep(10), schedule.total_asleep(99)));
+        assert_eq!((0, 10), (schedule.midnight_asleep(10), schedule.midnight_asleep(99)));
+        assert_eq!(Some(99), schedule.sleepiest_guard());
+    }
+
+    #[test]
+    fn unfinished_naps_run_to_the_end_of_the_hour() {
+        let log = "\
+[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:58] falls asleep
+[1518-11-02 00:00] Guard #11 begins shift
+[1518-11-02 00:10] falls asleep
+[1518-11-02 00:20] falls asleep
+[1518-11-02 00:30] wakes up";
+        let schedule = SleepSchedule::from_events(&parse_log(log).unwrap());
+
+        assert_eq!(2, schedule.total_asleep(10));
+        // the second sleep doesn't restart the nap
+        assert_eq!(20, schedule.total_asleep(11));
+    }
+}
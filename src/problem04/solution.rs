@@ -1,151 +1,65 @@
 use super::input::INPUT;
+use super::log::{parse_log, GuardEvent};
+use super::schedule::SleepSchedule;
 
-pub fn part1() {
-    println!("Problem 4, Part 1: {}", solve_1(INPUT))
+pub fn part1() -> String {
+    format!("Problem 4, Part 1: {}", solve_1(INPUT))
 }
 
-pub fn part2() {
-    println!("Problem 4, Part 2: {}", solve_2(INPUT))
+pub fn part2() -> String {
+    format!("Problem 4, Part 2: {}", solve_2(INPUT))
 }
 
-fn solve_1(input: &str) -> usize {
-    let events = sort_events(input);
-    let sleepiest_guard = find_sleepiest_guard(&events);
-    let most_slept_minute = find_most_frequently_slept_minute(&events, sleepiest_guard);
-    sleepiest_guard as usize * most_slept_minute as usize
-}
-
-fn solve_2(input: &str) -> usize {
-    let events = sort_events(input);
-
-    use std::collections::BTreeMap;
-
-    let mut guard_minutes: BTreeMap<u16, [u16; 60]> = BTreeMap::new();
-    let mut current_guard_id = 0;
-    let mut current_sleep_time = 0;
-
-    let mut most_freq_slept_id = 0;
-    let mut most_freq_slept_minute = 0;
-    let mut most_freq_slept_freq = 0;
-
-    for event in events {
-
-        match event.as_bytes()[19] as char {
-            'G'/*uard #NN begins shift*/ => {
-                current_guard_id = parse_id(event);
-            }
-
-            'f'/*alls asleep*/ => {
-                current_sleep_time = parse_minute(event);
-            }
-
-            'w'/*akes up*/ => {
-                let wake_time = parse_minute(event);
-                let minutes = guard_minutes.entry(current_guard_id).or_insert([0; 60]);
-                for i in current_sleep_time..wake_time {
-                    let i = i as usize;
-                    minutes[i] += 1;
-
-                    let cur_freq = minutes[i];
-                    if cur_freq > most_freq_slept_freq {
-                        most_freq_slept_freq = cur_freq;
-                        most_freq_slept_minute = i;
-                        most_freq_slept_id = current_guard_id;
-                    }
-                }
-            }
-            _ => panic!()
-        }
+/// A per guard summary of [log], or of the puzzle input if there is none, followed by the timeline.
+/// Fails with the first line that doesn't parse
+pub fn stats(log: Option<&str>) -> Result<String, String> {
+    let events = parse_log(log.unwrap_or(INPUT)).map_err(|e| e.to_string())?;
+    let schedule = SleepSchedule::from_events(&events);
+
+    let mut out = String::from("Guard     Shifts  Asleep  Average  Minute\n");
+    for guard in schedule.guards() {
+        let minute = match schedule.most_frequent_minute(guard) {
+            Some((minute, nights)) => format!("{:02} ({}x)", minute, nights),
+            None => "-".to_string(),
+        };
+        out += &format!("{:<8}  {:>6}  {:>6}  {:>7.1}  {}\n",
+                        format!("#{}", guard),
+                        schedule.shift_count(guard),
+                        schedule.total_asleep(guard),
+                        schedule.average_asleep(guard).unwrap_or(0.0),
+                        minute);
     }
 
-    most_freq_slept_minute * most_freq_slept_id as usize
-}
-
-fn find_sleepiest_guard(events: &[&str]) -> u16 {
-    use std::collections::BTreeMap;
-
-    let mut guard_times: BTreeMap<u16, u16> = BTreeMap::new();
-    let mut current_guard_id = 0;
-    let mut current_sleep_time = 0;
-
-    let mut highest_sleep_id = 0;
-    let mut highest_sleep_minutes = 0;
-
-    for event in events {
-        match event.as_bytes()[19] as char {
-            'G'/*uard #NN begins shift*/ => {
-                current_guard_id = parse_id(event);
-            }
-
-            'f'/*alls asleep*/ => {
-                current_sleep_time = parse_minute(event);
-            }
-
-            'w'/*akes up*/ => {
-                let elapsed_sleep = parse_minute(event) - current_sleep_time;
-                let entry_value = guard_times.entry(current_guard_id).or_insert(0);
-                let total_sleep = *entry_value + elapsed_sleep as u16;
-                *entry_value = total_sleep;
-
-                if total_sleep > highest_sleep_minutes {
-                    highest_sleep_minutes = total_sleep;
-                    highest_sleep_id = current_guard_id
-                }
-            }
-            _ => panic!()
-        }
-    }
-
-    highest_sleep_id
-}
+    let distribution: Vec<String> = schedule.shift_count_distribution().iter()
+        .map(|(shifts, guards)| format!("{} shifts: {}", shifts, guards))
+        .collect();
+    out += &format!("\nGuards by shift count: {}\n", distribution.join(", "));
 
-fn find_most_frequently_slept_minute(events: &[&str], id: u16) -> u8 {
-    let mut current_guard_id = 0;
-    let mut current_sleep_time = 0;
-
-    let mut minutes: [u16; 60] = [0; 60];
-
-    for event in events {
-        match event.as_bytes()[19] as char {
-            'G'/*uard #NN begins shift*/ => {
-                current_guard_id = parse_id(event);
-            }
-
-            'f'/*alls asleep*/ => {
-                if current_guard_id == id {
-                    current_sleep_time = parse_minute(event);
-                }
-            }
-
-            'w'/*akes up*/ => {
-                if current_guard_id == id {
-                    let wake_time = parse_minute(event);
-                    for i in current_sleep_time..wake_time {
-                        minutes[i as usize] += 1;
-                    }
-                }
-            }
-            _ => panic!()
-        }
+    if let (Some(most), Some(least)) = (schedule.most_reliable_guard(), schedule.least_reliable_guard()) {
+        out += &format!("Most reliable: #{}\nLeast reliable: #{}\n", most, least);
     }
 
-    minutes.iter().enumerate().max_by_key(|(_, total)| { *total }).unwrap().0 as u8
+    Ok(out + "\n" + &schedule.timeline())
 }
 
-fn parse_id(event: &str) -> u16 {
-    event[26..].split_whitespace().next().unwrap().parse().unwrap()
+fn solve_1(input: &str) -> usize {
+    let schedule = SleepSchedule::from_events(&sort_events(input));
+    let sleepiest_guard = schedule.sleepiest_guard().expect("no guards in the log");
+    let (most_slept_minute, _) = schedule.most_frequent_minute(sleepiest_guard).expect("the sleepiest guard never slept");
+    sleepiest_guard as usize * most_slept_minute
 }
 
-fn parse_minute(event: &str) -> u8 {
-    event[15..=16].parse().unwrap()
+fn solve_2(input: &str) -> usize {
+    let schedule = SleepSchedule::from_events(&sort_events(input));
+    let (guard, minute) = schedule.most_frequent_sleeper().expect("no guard ever slept");
+    guard as usize * minute
 }
 
-fn sort_events(input: &str) -> Vec<&str> {
-    let mut vec: Vec<&str> = input.lines().collect();
-
-    vec.sort();
-
-    vec
+fn sort_events(input: &str) -> Vec<GuardEvent> {
+    match parse_log(input) {
+        Ok(events) => events,
+        Err(e) => panic!("invalid guard log, {}", e),
+    }
 }
 
 #[cfg(test)]
@@ -157,11 +71,70 @@ mod tests {
         assert_eq!(240, solve_1(SAMPLE_INPUT))
     }
 
+    #[test]
+    fn part_1_ignores_naps_outside_the_midnight_hour() {
+        // #10 sleeps longer, but only #99 sleeps during the hour the puzzle counts
+        let log = "\
+[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 03:00] falls asleep
+[1518-11-01 05:00] wakes up
+[1518-11-02 00:00] Guard #99 begins shift
+[1518-11-02 00:10] falls asleep
+[1518-11-02 00:20] wakes up
+[1518-11-03 00:00] Guard #99 begins shift
+[1518-11-03 00:15] falls asleep
+[1518-11-03 00:16] wakes up";
+        assert_eq!(99 * 15, solve_1(log));
+    }
+
     #[test]
     fn part_2_is_correct() {
         assert_eq!(4455, solve_2(SAMPLE_INPUT))
     }
 
+    #[test]
+    fn input_is_correct() {
+        assert_eq!(8950, solve_1(INPUT));
+        assert_eq!(78452, solve_2(INPUT));
+    }
+
+    #[test]
+    fn shuffled_and_spaced_log() {
+        let mut lines: Vec<String> = SAMPLE_INPUT.lines()
+            .map(|line| line.replace("] ", "]   ").replace("#10", "#0010"))
+            .collect();
+        lines.reverse();
+        let log = lines.join("\n");
+
+        assert_eq!(240, solve_1(&log));
+        assert_eq!(4455, solve_2(&log));
+    }
+
+    #[test]
+    fn stats_sample() {
+        let stats = stats(Some(SAMPLE_INPUT)).unwrap();
+
+        assert!(stats.starts_with("\
+Guard     Shifts  Asleep  Average  Minute
+#10            2      50     25.0  24 (2x)
+#99            3      30     10.0  45 (3x)
+
+Guards by shift count: 2 shifts: 1, 3 shifts: 1
+Most reliable: #99
+Least reliable: #10
+
+Date   ID   Minute
+"));
+        assert!(stats.ends_with("11-05  #99  .............................................##########.....\n"));
+    }
+
+    #[test]
+    fn stats_reports_the_bad_line() {
+        let log = "[1518-11-01 00:00] Guard #10 begins shift\n[1518-11-01 00:05 falls asleep";
+        assert_eq!(Err(parse_log(log).unwrap_err().to_string()), stats(Some(log)));
+        assert!(stats(Some(log)).unwrap_err().starts_with("line 2: "));
+    }
+
     pub const SAMPLE_INPUT: &str = "\
 [1518-11-01 00:00] Guard #10 begins shift
 [1518-11-01 00:05] falls asleep
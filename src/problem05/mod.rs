@@ -1,5 +1,8 @@
 mod solution;
 mod input;
+#[allow(dead_code)]
+mod rules;
 
 pub use self::solution::part1;
-pub use self::solution::part2;
\ No newline at end of file
+pub use self::solution::part2;
+pub use self::solution::{react_stream, react_stream_into, stats};
\ No newline at end of file
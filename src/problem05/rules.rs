@@ -0,0 +1,165 @@
+//! Which units annihilate each other when they end up adjacent
+
+use ::std::collections::HashSet;
+use ::std::fmt;
+use ::std::hash::Hash;
+use ::math::SubAbs;
+
+pub trait ReactionRules<U> {
+    /// Whether [left] and [right] annihilate when [left] sits directly before [right]
+    fn reacts(&self, left: &U, right: &U) -> bool;
+}
+
+/// The puzzle's rule: the same letter in opposite cases
+pub struct OppositePolarity;
+
+const REACTION_DIFFERENCE: u8 = b'a' - b'A';
+
+impl ReactionRules<u8> for OppositePolarity {
+    fn reacts(&self, left: &u8, right: &u8) -> bool {
+        left.is_ascii_alphabetic() && right.is_ascii_alphabetic() && left.sub_abs(*right) == REACTION_DIFFERENCE
+    }
+}
+
+impl ReactionRules<char> for OppositePolarity {
+    fn reacts(&self, left: &char, right: &char) -> bool {
+        left != right && left.to_lowercase().eq(right.to_lowercase())
+    }
+}
+
+/// Any `Fn(&U, &U) -> bool` closure is a set of rules
+impl<U, F: Fn(&U, &U) -> bool> ReactionRules<U> for F {
+    fn reacts(&self, left: &U, right: &U) -> bool {
+        self(left, right)
+    }
+}
+
+/// An explicit table of reacting pairs. Pairs react in either order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairTable<U: Eq + Hash> {
+    pairs: HashSet<(U, U)>,
+}
+
+impl<U: Eq + Hash + Clone> PairTable<U> {
+    pub fn new() -> PairTable<U> {
+        PairTable { pairs: HashSet::new() }
+    }
+
+    pub fn insert(&mut self, a: U, b: U) {
+        self.pairs.insert((b.clone(), a.clone()));
+        self.pairs.insert((a, b));
+    }
+}
+
+impl<U: Eq + Hash + Clone> ReactionRules<U> for PairTable<U> {
+    fn reacts(&self, left: &U, right: &U) -> bool {
+        self.pairs.contains(&(left.clone(), right.clone()))
+    }
+}
+
+impl PairTable<char> {
+    /// Parse rules written one pair per line as two units separated by whitespace. Units are
+    /// single characters, blank lines and lines starting with `#` are skipped.
+    ///
+    /// ```text
+    /// # matter and antimatter
+    /// α β
+    /// ☉ ☽
+    /// ```
+    pub fn parse(text: &str) -> Result<PairTable<char>, ParseRulesError> {
+        let mut table = PairTable::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |reason| ParseRulesError { line: i + 1, reason };
+
+            let units: Vec<&str> = line.split_whitespace().collect();
+            let (a, b) = match units[..] {
+                [a, b] => (single(a).ok_or(error("expected a single character unit"))?,
+                           single(b).ok_or(error("expected a single character unit"))?),
+                _ => return Err(error("expected two units")),
+            };
+
+            table.insert(a, b);
+        }
+
+        Ok(table)
+    }
+}
+
+fn single(unit: &str) -> Option<char> {
+    let mut chars = unit.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseRulesError {
+    /// 1 based
+    pub line: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ParseRulesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opposite_polarity() {
+        assert!(OppositePolarity.reacts(&b'a', &b'A'));
+        assert!(OppositePolarity.reacts(&b'Z', &b'z'));
+        assert!(!OppositePolarity.reacts(&b'a', &b'a'));
+        assert!(!OppositePolarity.reacts(&b'a', &b'B'));
+        // 32 apart but not letters
+        assert!(!OppositePolarity.reacts(&b'@', &b'`'));
+        // a letter and a non letter 32 apart, either way round
+        assert!(!OppositePolarity.reacts(&b'Q', &b'1'));
+        assert!(!OppositePolarity.reacts(&b'A', &b'!'));
+        assert!(!OppositePolarity.reacts(&b'1', &b'Q'));
+        assert!(!OppositePolarity.reacts(&b'!', &b'A'));
+
+        assert!(OppositePolarity.reacts(&'ä', &'Ä'));
+        assert!(!OppositePolarity.reacts(&'ä', &'A'));
+    }
+
+    #[test]
+    fn pair_tables_react_both_ways() {
+        let mut table = PairTable::new();
+        table.insert('α', 'β');
+
+        assert!(table.reacts(&'α', &'β'));
+        assert!(table.reacts(&'β', &'α'));
+        assert!(!table.reacts(&'α', &'α'));
+    }
+
+    #[test]
+    fn parse() {
+        let table = PairTable::parse("# comment\n\n  α β \nx\tY\n").unwrap();
+
+        let mut expected = PairTable::new();
+        expected.insert('α', 'β');
+        expected.insert('x', 'Y');
+        assert_eq!(expected, table);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Err(ParseRulesError { line: 2, reason: "expected two units" }),
+                   PairTable::parse("a A\nb B c\n"));
+        assert_eq!(Err(ParseRulesError { line: 1, reason: "expected a single character unit" }),
+                   PairTable::parse("ab AB"));
+        assert_eq!("line 1: expected two units", PairTable::parse("a").unwrap_err().to_string());
+    }
+}
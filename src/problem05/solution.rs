@@ -1,59 +1,284 @@
-use ::math::SubAbs;
+use ::std::collections::{BTreeMap, BTreeSet};
+use ::std::io::{self, BufRead, Write};
 use super::input::POLYMER;
+use super::rules::{OppositePolarity, ReactionRules};
+#[cfg(feature = "parallel")]
+use ::parallel;
 
-pub fn part1() {
-    println!("Problem 5, Part 1: {}", length_of_polymer_after_reaction(POLYMER))
+#[cfg(not(feature = "parallel"))]
+pub fn part1() -> String {
+    format!("Problem 5, Part 1: {}", length_of_polymer_after_reaction(POLYMER))
 }
 
-pub fn part2() {
-    println!("Problem 5, Part 2: {}", length_of_best_polymer(POLYMER))
+#[cfg(feature = "parallel")]
+pub fn part1() -> String {
+    format!("Problem 5, Part 1: {}", length_of_polymer_after_reaction_parallel(POLYMER))
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn part2() -> String {
+    format!("Problem 5, Part 2: {}", length_of_best_polymer(POLYMER))
+}
+
+#[cfg(feature = "parallel")]
+pub fn part2() -> String {
+    format!("Problem 5, Part 2: {}", length_of_best_polymer_parallel(POLYMER))
 }
 
 fn react(polymer: impl Iterator<Item = u8>) -> String {
-    let mut stack: Vec<u8> = Vec::new();
+    let stack = react_with(polymer, &OppositePolarity, None);
+    String::from_utf8(stack).expect("output polymer was not valid utf8")
+}
+
+/// Fully react [polymer] under [rules], leaving the surviving units in order. Each unit only ever
+/// meets the unit on top of the stack of survivors before it. Every reaction is recorded in
+/// [trace] if one is given
+fn react_with<U, R: ReactionRules<U>>(polymer: impl Iterator<Item = U>, rules: &R, trace: Option<&mut Trace<U>>) -> Vec<U> {
+    let mut stack: Vec<U> = Vec::new();
+    react_onto(&mut stack, polymer, rules, trace);
+    stack
+}
+
+/// Continue reacting with [stack] as the survivors so far. A [trace] must have followed the
+/// stack since it was empty
+fn react_onto<U, R: ReactionRules<U>>(stack: &mut Vec<U>, polymer: impl Iterator<Item = U>, rules: &R, mut trace: Option<&mut Trace<U>>) {
     for c in polymer {
-        match stack.last() {
-            None => stack.push(c),
-            Some(&d) => if has_reaction(c, d) {
-                stack.pop();
-            } else {
-                stack.push(c);
+        let reacts = match stack.last() {
+            None => false,
+            Some(d) => rules.reacts(d, &c),
+        };
+
+        if reacts {
+            let d = stack.pop().unwrap();
+            if let Some(trace) = trace.as_mut() {
+                trace.annihilated(d, c);
             }
+        } else {
+            stack.push(c);
+            if let Some(trace) = trace.as_mut() {
+                trace.pushed(stack.len());
+            }
+        }
+    }
+}
+
+/// Two units that annihilated, with their positions in the source polymer
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Annihilation<U> {
+    left: usize,
+    left_unit: U,
+    right: usize,
+    right_unit: U,
+}
+
+/// Everything that happened while reacting a polymer
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Trace<U> {
+    /// The position in the source polymer of each unit on the stack
+    survivors: Vec<usize>,
+    /// Every reaction in the order it happened
+    events: Vec<Annihilation<U>>,
+    /// The most units ever waiting on the stack
+    max_depth: usize,
+    /// How many units have been reacted so far
+    read: usize,
+}
+
+impl<U> Trace<U> {
+    fn new() -> Trace<U> {
+        Trace { survivors: Vec::new(), events: Vec::new(), max_depth: 0, read: 0 }
+    }
+
+    /// The next unit survived for now, leaving [depth] units on the stack
+    fn pushed(&mut self, depth: usize) {
+        self.survivors.push(self.read);
+        self.max_depth = self.max_depth.max(depth);
+        self.read += 1;
+    }
+
+    /// The next unit annihilated the top of the stack
+    fn annihilated(&mut self, left_unit: U, right_unit: U) {
+        let left = self.survivors.pop().expect("the trace did not follow the stack");
+        self.events.push(Annihilation { left, left_unit, right: self.read, right_unit });
+        self.read += 1;
+    }
+
+    /// The position in the source polymer of each surviving unit
+    fn source_indices(&self) -> &[usize] {
+        &self.survivors
+    }
+
+    /// The reaction between the units furthest apart in the source polymer
+    fn widest(&self) -> Option<&Annihilation<U>> {
+        self.events.iter().max_by_key(|event| event.right - event.left)
+    }
+}
+
+impl<U: Ord + Clone> Trace<U> {
+    /// How many units of each kind were annihilated
+    fn reactions(&self) -> BTreeMap<U, usize> {
+        let mut counts = BTreeMap::new();
+        for event in &self.events {
+            *counts.entry(event.left_unit.clone()).or_insert(0) += 1;
+            *counts.entry(event.right_unit.clone()).or_insert(0) += 1;
         }
+        counts
+    }
+}
+
+impl Trace<u8> {
+    /// How many reactions each unit type (as its lower case letter) took part in
+    fn reactions_by_type(&self) -> BTreeMap<u8, usize> {
+        let mut counts = BTreeMap::new();
+        for event in &self.events {
+            *counts.entry(event.left_unit.to_ascii_lowercase()).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+/// How many survivor positions [stats] lists before counting the rest
+const LISTED_SOURCES: usize = 10;
+
+/// Summarise how the polymer reacts, or [polymer] instead of the puzzle input
+pub fn stats(polymer: Option<&str>) -> String {
+    let polymer = polymer.unwrap_or(POLYMER);
+    let mut trace = Trace::new();
+    let units = polymer.bytes().filter(|c| !c.is_ascii_whitespace());
+    let survivors = react_with(units, &OppositePolarity, Some(&mut trace));
+
+    let counts = |counts: BTreeMap<u8, usize>| counts.iter()
+        .map(|(&unit, count)| format!("{} {}", unit as char, count))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let sources = trace.source_indices();
+    let mut listed: Vec<String> = sources.iter().take(LISTED_SOURCES).map(|i| i.to_string()).collect();
+    if sources.len() > LISTED_SOURCES {
+        listed.push(format!("... {} more", sources.len() - LISTED_SOURCES));
+    }
+
+    let mut out = format!("Units: {}\nSurvivors: {}\nSurvivors from: {}\nReactions: {}\nDeepest stack: {}\n",
+                          trace.read, survivors.len(), listed.join(", "), trace.events.len(), trace.max_depth);
+    if let Some(widest) = trace.widest() {
+        out += &format!("Widest reaction: {}{} at {} and {}\n",
+                        widest.left_unit as char, widest.right_unit as char, widest.left, widest.right);
+    }
+    out += &format!("Reactions by type: {}\n", counts(trace.reactions_by_type()));
+    out + &format!("Reactions by unit: {}\n", counts(trace.reactions()))
+}
+
+/// [react] with the polymer cut into [chunks] pieces that are reacted on their own threads.
+///
+/// A reacted chunk can only still react at its ends, so the survivors of each chunk are fed in
+/// order through the same stack reactor, cancelling the end of what came before against the start
+/// of the next chunk. The result is identical to [react] for rules like [OppositePolarity] where
+/// every unit has a single partner, as the order reactions happen in then doesn't matter.
+#[cfg(feature = "parallel")]
+fn react_chunked(polymer: &[u8], chunks: usize) -> String {
+    let reduced = parallel::map_chunks(polymer.len(), chunks, |range| {
+        react_with(polymer[range].iter().cloned(), &OppositePolarity, None)
+    });
+
+    let mut stack: Vec<u8> = Vec::new();
+    for chunk in reduced {
+        react_onto(&mut stack, chunk.into_iter(), &OppositePolarity, None);
     }
     String::from_utf8(stack).expect("output polymer was not valid utf8")
 }
 
+/// Fully react the polymer read from [input] and return its length. Only the survivors are kept in
+/// memory, never the source, and whitespace such as a trailing newline is skipped
+pub fn react_stream(input: impl BufRead) -> io::Result<usize> {
+    react_reader(input).map(|stack| stack.len())
+}
+
+/// [react_stream], also writing the surviving polymer to [sink]
+pub fn react_stream_into(input: impl BufRead, sink: &mut impl Write) -> io::Result<usize> {
+    let stack = react_reader(input)?;
+    sink.write_all(&stack)?;
+    sink.flush()?;
+    Ok(stack.len())
+}
+
+fn react_reader(mut input: impl BufRead) -> io::Result<Vec<u8>> {
+    let mut stack: Vec<u8> = Vec::new();
+
+    loop {
+        let consumed = {
+            let buf = input.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+
+            let units = buf.iter().cloned().filter(|c| !c.is_ascii_whitespace());
+            react_onto(&mut stack, units, &OppositePolarity, None);
+            buf.len()
+        };
+        input.consume(consumed);
+    }
+
+    Ok(stack)
+}
+
+#[cfg(any(not(feature = "parallel"), test))]
 fn length_of_polymer_after_reaction(polymer: &str) -> usize {
     react(polymer.bytes()).len()
 }
 
-const ALPHA_LOWER: &str = "abcdefghijklmnopqrstuvwzyz";
-const ALPHA_UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUBWZYZ";
+/// [length_of_polymer_after_reaction] with the polymer cut into one chunk per thread
+#[cfg(feature = "parallel")]
+fn length_of_polymer_after_reaction_parallel(polymer: &str) -> usize {
+    react_chunked(polymer.as_bytes(), parallel::threads()).len()
+}
 
-fn length_of_best_polymer(polymer: &str) -> usize {
+/// The length of the fully reacted polymer after removing each unit type (both polarities) that
+/// survives the initial reaction, ordered by unit
+#[cfg(any(not(feature = "parallel"), test))]
+fn removal_table(polymer: &str) -> Vec<(char, usize)> {
     let initial = react(polymer.bytes());
 
-    ALPHA_LOWER.bytes()
-        .zip(ALPHA_UPPER.bytes())
-        .map(|(lower, upper)| {
-            let cand = initial.bytes()
-                .filter(|&c| { c != lower && c != upper });
-            react(cand).len()
-        })
+    unit_types(&initial).into_iter()
+        .map(|unit| (unit as char, length_without(&initial, unit)))
+        .collect()
+}
+
+#[cfg(any(not(feature = "parallel"), test))]
+fn length_of_best_polymer(polymer: &str) -> usize {
+    removal_table(polymer).into_iter()
+        .map(|(_, len)| len)
         .min()
-        .unwrap()
+        .unwrap_or(0)
 }
 
-// Helpers
-// =======
+/// [length_of_best_polymer] with the unit types split between threads
+#[cfg(feature = "parallel")]
+fn length_of_best_polymer_parallel(polymer: &str) -> usize {
+    let initial = react(polymer.bytes());
+    let units: Vec<u8> = unit_types(&initial).into_iter().collect();
 
-const REACTION_DIFFERENCE: u8 = 'a' as u8 - 'A' as u8;
+    parallel::map_ranges(units.len(), |range| {
+        units[range].iter()
+            .map(|&unit| length_without(&initial, unit))
+            .min()
+            .unwrap()
+    })
+        .into_iter()
+        .min()
+        .unwrap_or(0)
+}
 
-fn has_reaction(left: u8, right: u8) -> bool {
-    left.sub_abs(right) == REACTION_DIFFERENCE
+/// Every unit type in [polymer], as its lower case letter
+fn unit_types(polymer: &str) -> BTreeSet<u8> {
+    polymer.bytes().map(|c| c.to_ascii_lowercase()).collect()
 }
 
+/// The reacted length of [polymer] with every unit of type [unit] removed
+fn length_without(polymer: &str, unit: u8) -> usize {
+    let cand = polymer.bytes()
+        .filter(|&c| { c.to_ascii_lowercase() != unit });
+    react(cand).len()
+}
 
 #[cfg(test)]
 mod tests {
@@ -81,6 +306,238 @@ mod tests {
     fn part_2_is_correct() {
         assert_eq!(5492, length_of_best_polymer(POLYMER))
     }
+
+    #[test]
+    fn removal_table_sample() {
+        let expected = vec![('a', 6), ('b', 8), ('c', 4), ('d', 6)];
+        assert_eq!(expected, removal_table(SAMPLE_INPUT));
+    }
+
+    #[test]
+    fn removal_table_covers_every_letter() {
+        let table = removal_table("abcdefghijklmnopqrstuvwxyz");
+
+        let units: String = table.iter().map(|&(unit, _)| unit).collect();
+        assert_eq!("abcdefghijklmnopqrstuvwxyz", units);
+        assert!(table.iter().all(|&(_, len)| len == 25));
+
+        // upper case units are tabled under their lower case letter
+        assert_eq!(vec![('v', 1), ('x', 1)], removal_table("VX"));
+    }
+
+    #[test]
+    fn removing_x_or_v_is_tried() {
+        // only removing x lets the rest collapse
+        assert_eq!(vec![('a', 5), ('b', 5), ('v', 5), ('x', 0)], removal_table("aVbXBvA"));
+        assert_eq!(0, length_of_best_polymer("aVbXBvA"));
+
+        assert_eq!(vec![('a', 0), ('v', 4), ('x', 4)], removal_table("vXaxVA"));
+    }
+
+    #[test]
+    fn custom_rules() {
+        use super::super::rules::PairTable;
+
+        // only the table's pairs react, in either order, whatever their case
+        let rules = PairTable::parse("α β\nx Y").unwrap();
+        let polymer = "aαββαxYAa";
+        let actual: String = react_with(polymer.chars(), &rules, None).into_iter().collect();
+        assert_eq!("aAa", actual);
+
+        let actual: String = react_with("αΑβΒ".chars(), &OppositePolarity, None).into_iter().collect();
+        assert_eq!("", actual);
+        let actual: String = react_with("ÄäxÉé".chars(), &OppositePolarity, None).into_iter().collect();
+        assert_eq!("x", actual);
+
+        // a closure: digits cancel with their complement to 9
+        let nines = |a: &u8, b: &u8| a + b == b'0' + b'9';
+        assert_eq!(b"7".to_vec(), react_with(b"18457".iter().cloned(), &nines, None));
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn chunked_matches_react() {
+        for &chunks in &[1, 2, 3, 7, 64] {
+            assert_eq!(react(POLYMER.bytes()), react_chunked(POLYMER.as_bytes(), chunks));
+            assert_eq!(react(SAMPLE_INPUT.bytes()), react_chunked(SAMPLE_INPUT.as_bytes(), chunks));
+        }
+
+        // reactions that cascade across many chunk boundaries
+        let nested = "abcdefgh".to_string() + "HGFEDCBA";
+        assert_eq!("", react_chunked(nested.as_bytes(), 16));
+        assert_eq!("", react_chunked(b"", 4));
+
+        // a pseudo random polymer over few unit types reacts a lot
+        let mut seed: u32 = 7;
+        let noise: Vec<u8> = (0..10_000).map(|_| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            b"aAbB"[(seed >> 16) as usize % 4]
+        }).collect();
+        for &chunks in &[2, 5, 33] {
+            assert_eq!(react(noise.iter().cloned()), react_chunked(&noise, chunks));
+        }
+    }
+
+    #[test]
+    fn streams_match_react() {
+        use std::io::{BufReader, Cursor};
+
+        assert_eq!(11242, react_stream(POLYMER.as_bytes()).unwrap());
+
+        // a tiny buffer so reactions cross reads
+        let reader = BufReader::with_capacity(3, Cursor::new(SAMPLE_INPUT.to_string() + "\r\n"));
+        let mut sink: Vec<u8> = Vec::new();
+        assert_eq!(10, react_stream_into(reader, &mut sink).unwrap());
+        assert_eq!(b"dabCBAcaDA".to_vec(), sink);
+
+        let reader = BufReader::with_capacity(1, "aB\nbA \n".as_bytes());
+        assert_eq!(0, react_stream(reader).unwrap());
+
+        // only letters react, even with a non letter 32 away
+        assert_eq!(2, react_stream("Q1aA".as_bytes()).unwrap());
+        assert_eq!(2, react_stream("A!".as_bytes()).unwrap());
+    }
+
+    /// An endless `aAaA...` source
+    struct Annihilating;
+
+    impl io::Read for Annihilating {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            for (i, c) in buf.iter_mut().enumerate() {
+                *c = if i % 2 == 0 { b'a' } else { b'A' };
+            }
+            Ok(buf.len() / 2 * 2)
+        }
+    }
+
+    /// Counts what passes through a reader and the most asked for in one read
+    struct Counting<R> {
+        inner: R,
+        total: usize,
+        largest_read: usize,
+    }
+
+    impl<R: io::Read> io::Read for Counting<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.largest_read = self.largest_read.max(buf.len());
+            let n = self.inner.read(buf)?;
+            self.total += n;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn streams_do_not_hold_the_source() {
+        use std::io::{BufReader, Read};
+
+        // 16MB read through an 8KB buffer, but the survivors never grow past a single unit
+        let mut source = Counting { inner: Annihilating.take(16 << 20), total: 0, largest_read: 0 };
+        let stack = react_reader(BufReader::with_capacity(8 << 10, &mut source)).unwrap();
+
+        assert!(stack.is_empty());
+        // a Vec never shrinks, so its capacity bounds how many units were ever held at once
+        assert!(stack.capacity() <= 8, "held {} units", stack.capacity());
+        assert_eq!(16 << 20, source.total);
+        assert_eq!(8 << 10, source.largest_read);
+    }
+
+    /// React [polymer] under the puzzle's rules, keeping a trace
+    fn traced(polymer: &str) -> (Vec<u8>, Trace<u8>) {
+        let mut trace = Trace::new();
+        let survivors = react_with(polymer.bytes(), &OppositePolarity, Some(&mut trace));
+        (survivors, trace)
+    }
+
+    #[test]
+    fn trace_sample() {
+        let (survivors, trace) = traced(SAMPLE_INPUT);
+
+        assert_eq!(react(SAMPLE_INPUT.bytes()).into_bytes(), survivors);
+
+        // dabAcCaCBAcCcaDA -> dabAaCBAcCcaDA -> dabCBAcCcaDA -> dabCBAcaDA
+        assert_eq!(vec![
+            Annihilation { left: 4, left_unit: b'c', right: 5, right_unit: b'C' },
+            Annihilation { left: 3, left_unit: b'A', right: 6, right_unit: b'a' },
+            Annihilation { left: 10, left_unit: b'c', right: 11, right_unit: b'C' },
+        ], trace.events);
+
+        assert_eq!(&[0, 1, 2, 7, 8, 9, 12, 13, 14, 15], trace.source_indices());
+        assert_eq!(10, trace.max_depth);
+        assert_eq!(Some(&trace.events[1]), trace.widest());
+
+        let by_type: Vec<(u8, usize)> = trace.reactions_by_type().into_iter().collect();
+        assert_eq!(vec![(b'a', 1), (b'c', 2)], by_type);
+
+        let by_unit: Vec<(u8, usize)> = trace.reactions().into_iter().collect();
+        assert_eq!(vec![(b'A', 1), (b'C', 2), (b'a', 1), (b'c', 2)], by_unit);
+    }
+
+    #[test]
+    fn trace_depth_and_input() {
+        // the stack is deepest just before the nest collapses
+        let (survivors, trace) = traced("abcCBA");
+        assert_eq!(3, trace.max_depth);
+        assert!(survivors.is_empty());
+        assert!(trace.source_indices().is_empty());
+        assert_eq!(vec![(2, 3), (1, 4), (0, 5)],
+                   trace.events.iter().map(|e| (e.left, e.right)).collect::<Vec<_>>());
+
+        let (survivors, trace) = traced(POLYMER);
+        assert_eq!(11242, survivors.len());
+        assert_eq!(survivors.len(), trace.source_indices().len());
+        assert_eq!(POLYMER.len(), survivors.len() + 2 * trace.events.len());
+
+        let (_, trace) = traced("");
+        assert_eq!(0, trace.max_depth);
+        assert_eq!(None, trace.widest());
+    }
+
+    #[test]
+    fn trace_follows_the_stack_across_calls() {
+        let mut stack = Vec::new();
+        let mut trace = Trace::new();
+        for piece in &["dabAc", "CaCBAc", "CcaDA"] {
+            react_onto(&mut stack, piece.bytes(), &OppositePolarity, Some(&mut trace));
+        }
+
+        assert_eq!(traced(SAMPLE_INPUT), (stack, trace));
+    }
+
+    #[test]
+    fn stats_sample() {
+        assert_eq!("\
+Units: 16
+Survivors: 10
+Survivors from: 0, 1, 2, 7, 8, 9, 12, 13, 14, 15
+Reactions: 3
+Deepest stack: 10
+Widest reaction: Aa at 3 and 6
+Reactions by type: a 1, c 2
+Reactions by unit: A 1, C 2, a 1, c 2
+", stats(Some("dabAcCaCBAcCcaDA\n")));
+    }
+
+    #[test]
+    fn stats_lists_the_first_survivors() {
+        let stats = stats(Some("aAbcdefghijklm"));
+        assert!(stats.contains("\nSurvivors from: 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, ... 2 more\n"), "{}", stats);
+    }
+
+    #[test]
+    fn fully_reacting_polymer_has_no_candidates() {
+        assert!(removal_table("aBbA").is_empty());
+        assert_eq!(0, length_of_best_polymer("aBbA"));
+        assert_eq!(0, length_of_best_polymer(""));
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn parallel_agrees_with_serial() {
+        assert_eq!(4, length_of_best_polymer_parallel(SAMPLE_INPUT));
+        assert_eq!(5492, length_of_best_polymer_parallel(POLYMER));
+        assert_eq!(10, length_of_polymer_after_reaction_parallel(SAMPLE_INPUT));
+        assert_eq!(11242, length_of_polymer_after_reaction_parallel(POLYMER));
+    }
 }
 
 
@@ -97,4 +554,46 @@ mod bench {
         b.iter(|| react(POLYMER.bytes()));
     }
 
+    /// About 25MB, the puzzle input over and over with a unit between copies so they don't react
+    #[cfg(feature = "parallel")]
+    fn big_polymer() -> String {
+        let mut big = String::new();
+        while big.len() < 25_000_000 {
+            big.push_str(POLYMER);
+            big.push('q');
+        }
+        big
+    }
+
+    #[bench]
+    #[cfg(feature = "parallel")]
+    fn react_big_bench(b: &mut Bencher) {
+        let big = big_polymer();
+        b.iter(|| react(big.bytes()));
+    }
+
+    #[bench]
+    #[cfg(feature = "parallel")]
+    fn react_chunked_bench(b: &mut Bencher) {
+        b.iter(|| react_chunked(POLYMER.as_bytes(), parallel::threads()));
+    }
+
+    #[bench]
+    #[cfg(feature = "parallel")]
+    fn react_chunked_big_bench(b: &mut Bencher) {
+        let big = big_polymer();
+        b.iter(|| react_chunked(big.as_bytes(), parallel::threads()));
+    }
+
+    #[bench]
+    fn best_polymer_bench(b: &mut Bencher) {
+        b.iter(|| length_of_best_polymer(POLYMER));
+    }
+
+    #[bench]
+    #[cfg(feature = "parallel")]
+    fn best_polymer_parallel_bench(b: &mut Bencher) {
+        b.iter(|| length_of_best_polymer_parallel(POLYMER));
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,157 @@
+//! Distance metrics over integer positions in any number of dimensions
+
+/// A position on an integer lattice
+pub trait Position: Sized {
+    const DIMENSIONS: usize;
+
+    /// The coordinate along axis [i], `0 <= i < DIMENSIONS`
+    fn axis(&self, i: usize) -> i64;
+
+    /// ASSUMES `axes.len() == DIMENSIONS`
+    fn from_axes(axes: &[i64]) -> Self;
+}
+
+pub trait Metric<P> {
+    fn distance(&self, a: &P, b: &P) -> u64;
+
+    /// Whether a step outward from the bounding box of some points adds the same distance to every
+    /// one of them. Then a cell outside the box has the same nearest point as the closest cell on
+    /// the edge of the box, so a region is infinite exactly when it reaches the edge. False unless
+    /// proven for the metric
+    fn grows_uniformly_outside_the_box(&self) -> bool {
+        false
+    }
+}
+
+/// The sum of the per axis differences
+pub struct Manhattan;
+
+/// The largest per axis difference
+pub struct Chebyshev;
+
+/// The sum of the squared per axis differences. Orders points like the euclidean distance without
+/// leaving the integers
+pub struct SquaredEuclidean;
+
+impl<P: Position> Metric<P> for Manhattan {
+    fn distance(&self, a: &P, b: &P) -> u64 {
+        (0..P::DIMENSIONS)
+            .try_fold(0u64, |sum, i| sum.checked_add(a.axis(i).abs_diff(b.axis(i))))
+            .expect("manhattan distance overflows u64")
+    }
+
+    /// Every point lies inside the box, so a step outward along an axis moves one further from
+    /// every point along that axis and leaves the other axes alone
+    fn grows_uniformly_outside_the_box(&self) -> bool {
+        true
+    }
+}
+
+// not uniform: a step outward only adds to the points whose largest difference lies along that axis
+impl<P: Position> Metric<P> for Chebyshev {
+    fn distance(&self, a: &P, b: &P) -> u64 {
+        (0..P::DIMENSIONS)
+            .map(|i| a.axis(i).abs_diff(b.axis(i)))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+// not uniform: a step outward adds `2d + 1` for a point `d` away along that axis
+impl<P: Position> Metric<P> for SquaredEuclidean {
+    fn distance(&self, a: &P, b: &P) -> u64 {
+        (0..P::DIMENSIONS)
+            .try_fold(0u64, |sum, i| {
+                let d = a.axis(i).abs_diff(b.axis(i));
+                d.checked_mul(d).and_then(|square| sum.checked_add(square))
+            })
+            .expect("squared euclidean distance overflows u64")
+    }
+}
+
+/// Any `Fn(&P, &P) -> u64` closure is a metric
+impl<P, F: Fn(&P, &P) -> u64> Metric<P> for F {
+    fn distance(&self, a: &P, b: &P) -> u64 {
+        self(a, b)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Point3 {
+    pub x: i64,
+    pub y: i64,
+    pub z: i64,
+}
+
+impl Position for Point3 {
+    const DIMENSIONS: usize = 3;
+
+    fn axis(&self, i: usize) -> i64 {
+        match i {
+            0 => self.x,
+            1 => self.y,
+            2 => self.z,
+            _ => panic!("no axis {} in 3 dimensions", i),
+        }
+    }
+
+    fn from_axes(axes: &[i64]) -> Point3 {
+        Point3 { x: axes[0], y: axes[1], z: axes[2] }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(x: i64, y: i64, z: i64) -> Point3 {
+        Point3 { x, y, z }
+    }
+
+    #[test]
+    fn distances() {
+        let a = p(1, -2, 3);
+        let b = p(-3, 1, 3);
+
+        assert_eq!(7, Manhattan.distance(&a, &b));
+        assert_eq!(4, Chebyshev.distance(&a, &b));
+        assert_eq!(25, SquaredEuclidean.distance(&a, &b));
+        assert_eq!(0, Manhattan.distance(&a, &a));
+    }
+
+    #[test]
+    fn distances_at_the_limits() {
+        let far = p(i64::MIN, 0, 0);
+
+        assert_eq!(u64::MAX, Manhattan.distance(&far, &p(i64::MAX, 0, 0)));
+        assert_eq!(1 << 62, SquaredEuclidean.distance(&p(0, 0, 0), &p(1 << 31, 0, 0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "squared euclidean distance overflows u64")]
+    fn squared_euclidean_overflow_panics() {
+        SquaredEuclidean.distance(&p(0, 0, 0), &p(1 << 32, 0, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "manhattan distance overflows u64")]
+    fn manhattan_overflow_panics() {
+        Manhattan.distance(&p(i64::MIN, i64::MIN, 0), &p(i64::MAX, i64::MAX, 0));
+    }
+
+    #[test]
+    fn only_manhattan_grows_uniformly() {
+        let x_only = |a: &Point3, b: &Point3| a.x.abs_diff(b.x);
+
+        assert!(Metric::<Point3>::grows_uniformly_outside_the_box(&Manhattan));
+        assert!(!Metric::<Point3>::grows_uniformly_outside_the_box(&Chebyshev));
+        assert!(!Metric::<Point3>::grows_uniformly_outside_the_box(&SquaredEuclidean));
+        assert!(!x_only.grows_uniformly_outside_the_box());
+    }
+
+    #[test]
+    fn closures_are_metrics() {
+        let x_only = |a: &Point3, b: &Point3| a.x.abs_diff(b.x);
+        assert_eq!(4, x_only.distance(&p(1, 0, 0), &p(-3, 9, 9)));
+    }
+}
@@ -1,103 +1,543 @@
-use ::math::SubAbs;
+use ::std::io;
+use ::std::ops::RangeInclusive;
+use ::std::path::Path;
+use ::grid::{Coord, Grid};
+use ::image::{self, Rgb};
+use super::metric::{Chebyshev, Manhattan, Metric, Position, SquaredEuclidean};
+#[cfg(feature = "parallel")]
+use ::parallel;
 
 const INPUT: &str = include_str!("input.txt");
 const INPUT_THRESHOLD: usize = 10_000;
 
-pub fn part1() {
-    println!("Problem 6, Part 1: {}", solve_part_1(INPUT))
+pub fn part1() -> String {
+    format!("Problem 6, Part 1: {}", solve_part_1(INPUT))
 }
 
-pub fn part2() {
-    println!("Problem 6, Part 2: {}", solve_part_2(INPUT, INPUT_THRESHOLD))
+pub fn part2() -> String {
+    format!("Problem 6, Part 2: {}", solve_part_2(INPUT, INPUT_THRESHOLD))
 }
 
+/// Render the bounding box as an image, colouring each cell by its nearest point
+pub fn render(path: &Path) -> io::Result<()> {
+    image::save(&render_regions(&parse_points(INPUT), INPUT_THRESHOLD), path)
+}
+
+/// Part 1 and 2 over [input], or the puzzle input, under each metric. Part 2 only counts the cells
+/// of the bounding box
+pub fn stats(input: Option<&str>) -> Result<String, String> {
+    let points = try_parse_points(input.unwrap_or(INPUT))?;
+    let bb = bounding_box(points.iter()).ok_or("no points")?;
+
+    let mut out = format!("Points: {}\nBounding box: {}, {} to {}, {}\n", points.len(), bb.left, bb.top, bb.right, bb.bottom);
+    out += &metric_stats("Manhattan", &points, &Manhattan);
+    out += &metric_stats("Chebyshev", &points, &Chebyshev);
+    out += &metric_stats("Squared euclidean", &points, &SquaredEuclidean);
+
+    Ok(out)
+}
+
+#[cfg(not(feature = "parallel"))]
+fn metric_stats<M: Metric<Point>>(name: &str, points: &[Point], metric: &M) -> String {
+    describe_metric(name, part_1_bruteforce(points, metric), part_2_bruteforce(points, INPUT_THRESHOLD, metric))
+}
+
+#[cfg(feature = "parallel")]
+fn metric_stats<M: Metric<Point> + Sync>(name: &str, points: &[Point], metric: &M) -> String {
+    describe_metric(name, part_1_bruteforce_parallel(points, metric), part_2_bruteforce_parallel(points, INPUT_THRESHOLD, metric))
+}
+
+fn describe_metric(name: &str, area: Result<usize, &str>, safe: usize) -> String {
+    let area = match area {
+        Ok(area) => area.to_string(),
+        Err(e) => format!("unknown, {}", e),
+    };
+
+    format!("{}: largest finite area {}, {} cells in the box below {}\n", name, area, safe, INPUT_THRESHOLD)
+}
+
+#[cfg(not(feature = "parallel"))]
 fn solve_part_1(input: &str) -> usize {
-    part_1_bruteforce(&parse_points(input))
+    part_1_flood_fill(&parse_points(input))
 }
 
-fn part_1_bruteforce(points: &[Point]) -> usize {
-    let bb = bounding_box(points.iter()).expect("failed to create bounding box");
+/// Manhattan grows uniformly outside the box, so its regions always settle
+#[cfg(feature = "parallel")]
+fn solve_part_1(input: &str) -> usize {
+    part_1_bruteforce_parallel(&parse_points(input), &Manhattan).expect("manhattan regions did not settle")
+}
 
-    let w = bb.width();
-    let h = bb.height();
+/// Measure every region by checking the distance under [metric] from each cell of a box around the
+/// points to every point, calling a region infinite if it reaches the edge of the box.
+///
+/// For metrics that grow uniformly outside the box, like [Manhattan], the bounding box alone is
+/// exact: every point lies inside it, so a step outward from the edge adds the same distance to
+/// every point and the nearest point does not change. Under other metrics a region can leave the
+/// bounding box and still be finite, or only reach the edge far away, so the box is grown by its
+/// widest side and then by double that, until the finite regions stop changing between two
+/// margins. Gives up once the margin passes [MAX_GROWTH] times the widest side.
+#[cfg(any(not(feature = "parallel"), test))]
+fn part_1_bruteforce<P: Position, M: Metric<P>>(points: &[P], metric: &M) -> Result<usize, &'static str> {
+    largest_settled_area(points, metric, |min, max| tally_regions(points, metric, min, max))
+}
 
-    let mut closests: Vec<u16> = vec![0; points.len()];
+const MAX_GROWTH: i64 = 4;
+const UNSETTLED_REGIONS: &str = "the regions were still changing when the box stopped growing";
 
-    for y in 0..h {
-        for x in 0..w {
-            let cell = Point { x: x + bb.left, y: y + bb.top };
+/// [part_1_bruteforce] with each box cut into slabs along the slowest turning axis, one per
+/// thread. Each slab keeps its own tallies which are summed afterwards
+#[cfg(feature = "parallel")]
+fn part_1_bruteforce_parallel<P, M>(points: &[P], metric: &M) -> Result<usize, &'static str>
+    where P: Position + Sync,
+          M: Metric<P> + Sync
+{
+    largest_settled_area(points, metric, |min, max| {
+        let last = P::DIMENSIONS - 1;
+        let depth = (max[last] - min[last] + 1) as usize;
 
-            let mut nearest_idx = 0;
-            let mut min_dist = u16::max_value();
-            let mut dist_count = u16::max_value();
+        let tallies = parallel::map_ranges(depth, |slab| {
+            let slab = min[last] + slab.start as i64..=min[last] + slab.end as i64 - 1;
+            tally_regions_in(points, metric, min, max, slab)
+        });
 
-            for (i, point) in points.iter().enumerate() {
-                let dist = cell.manhattan_distance(&point);
+        let mut areas: Vec<usize> = vec![0; points.len()];
+        let mut infinite: Vec<bool> = vec![false; points.len()];
 
-                if dist < min_dist {
-                    nearest_idx = i;
-                    min_dist = dist;
-                    dist_count = 0;
-                } else if dist == min_dist {
-                    dist_count += 1;
-                }
+        for (slab_areas, slab_infinite) in tallies {
+            for i in 0..points.len() {
+                areas[i] += slab_areas[i];
+                infinite[i] |= slab_infinite[i];
             }
+        }
+
+        (areas, infinite)
+    })
+}
 
-            if dist_count == 0 && bb.contains(&points[nearest_idx]) {
-                closests[nearest_idx] += 1;
+/// Grow the bounding box of [points] until the finite regions [tally] reports stop changing, see
+/// [part_1_bruteforce]
+fn largest_settled_area<P, M>(points: &[P], metric: &M, tally: impl Fn(&[i64], &[i64]) -> Regions) -> Result<usize, &'static str>
+    where P: Position,
+          M: Metric<P>
+{
+    let (min, max) = bounds(points);
+
+    if metric.grows_uniformly_outside_the_box() {
+        let (areas, infinite) = tally(&min, &max);
+        return Ok(largest_finite_area(&areas, &infinite));
+    }
+
+    let widest = (0..P::DIMENSIONS).map(|i| max[i] - min[i]).max().unwrap_or(0).max(1);
+    let mut previous = None;
+    let mut margin = widest;
+
+    while margin <= MAX_GROWTH * widest {
+        let min: Vec<i64> = min.iter().map(|m| m - margin).collect();
+        let max: Vec<i64> = max.iter().map(|m| m + margin).collect();
+        let (areas, infinite) = tally(&min, &max);
+        let current = finite_areas(&areas, &infinite);
+
+        if previous.as_ref() == Some(&current) {
+            return Ok(largest_finite_area(&areas, &infinite));
+        }
+
+        previous = Some(current);
+        margin *= 2;
+    }
+
+    Err(UNSETTLED_REGIONS)
+}
+
+/// The area of every region and whether it reaches the edge of the box
+type Regions = (Vec<usize>, Vec<bool>);
+
+#[cfg(any(not(feature = "parallel"), test))]
+fn tally_regions<P: Position, M: Metric<P>>(points: &[P], metric: &M, min: &[i64], max: &[i64]) -> Regions {
+    let last = P::DIMENSIONS - 1;
+    tally_regions_in(points, metric, min, max, min[last]..=max[last])
+}
+
+/// [tally_regions] over the cells of the box whose last axis lies in [slab]
+fn tally_regions_in<P: Position, M: Metric<P>>(points: &[P], metric: &M, min: &[i64], max: &[i64], slab: RangeInclusive<i64>) -> Regions {
+    let mut areas: Vec<usize> = vec![0; points.len()];
+    let mut infinite: Vec<bool> = vec![false; points.len()];
+
+    for_each_cell_in(min, max, slab, |cell: P, on_edge| {
+        if let Some(i) = nearest(points, metric, &cell) {
+            areas[i] += 1;
+
+            if on_edge {
+                infinite[i] = true;
+            }
+        }
+    });
+
+    (areas, infinite)
+}
+
+/// The area of each finite region, None for the infinite ones
+fn finite_areas(areas: &[usize], infinite: &[bool]) -> Vec<Option<usize>> {
+    areas.iter()
+        .zip(infinite.iter())
+        .map(|(&area, &infinite)| if infinite { None } else { Some(area) })
+        .collect()
+}
+
+/// The index of the single point closest to [cell], or None on a tie
+fn nearest<P, M: Metric<P>>(points: &[P], metric: &M, cell: &P) -> Option<usize> {
+    let mut nearest_idx = 0;
+    let mut min_dist = u64::MAX;
+    let mut dist_count = u16::MAX;
+
+    for (i, point) in points.iter().enumerate() {
+        let dist = metric.distance(cell, point);
+
+        if dist < min_dist {
+            nearest_idx = i;
+            min_dist = dist;
+            dist_count = 0;
+        } else if dist == min_dist {
+            dist_count += 1;
+        }
+    }
+
+    if dist_count == 0 { Some(nearest_idx) } else { None }
+}
+
+fn largest_finite_area(areas: &[usize], infinite: &[bool]) -> usize {
+    areas.iter()
+        .zip(infinite.iter())
+        .filter(|&(_, &infinite)| !infinite)
+        .map(|(&area, _)| area)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Label the bounding box with a flood fill, then measure every region that does not reach the
+/// edge of the box. Runs in O(w·h) rather than O(w·h·n)
+#[cfg(any(not(feature = "parallel"), test))]
+fn part_1_flood_fill(points: &[Point]) -> usize {
+    let labels = voronoi(points);
+    let (w, h) = (labels.width(), labels.height());
+
+    let mut areas = vec![0; points.len()];
+    let mut infinite = vec![false; points.len()];
+
+    for (x, y) in labels.coords() {
+        if let Nearest::Point(i) = labels[(x, y)] {
+            areas[i] += 1;
+
+            // a region touching the edge of the box keeps growing outside of it
+            if x == 0 || y == 0 || x == w - 1 || y == h - 1 {
+                infinite[i] = true;
             }
         }
     }
 
-    *closests.iter().max().unwrap_or(&0) as usize
+    largest_finite_area(&areas, &infinite)
 }
 
 fn solve_part_2(input: &str, threshold: usize) -> usize {
-    part_2_bruteforce(&parse_points(input), threshold)
+    let coords: Vec<(i64, i64)> = parse_points(input).iter()
+        .map(|p| (p.x, p.y))
+        .collect();
+
+    part_2_separable(&coords, threshold)
+}
+
+/// Count the cells of the bounding box (edges included) whose total distance under [metric] is
+/// below [threshold]. Cells outside the box are not considered
+#[cfg(any(not(feature = "parallel"), test))]
+fn part_2_bruteforce<P: Position, M: Metric<P>>(points: &[P], threshold: usize, metric: &M) -> usize {
+    let mut safe_count = 0;
+
+    for_each_cell(points, |cell, _| {
+        if is_safe(points, threshold as u64, metric, &cell) {
+            safe_count += 1;
+        }
+    });
+
+    safe_count
+}
+
+/// [part_2_bruteforce] with the bounding box cut into slabs along the slowest turning axis, one
+/// per thread
+#[cfg(feature = "parallel")]
+fn part_2_bruteforce_parallel<P, M>(points: &[P], threshold: usize, metric: &M) -> usize
+    where P: Position + Sync,
+          M: Metric<P> + Sync
+{
+    let (min, max) = bounds(points);
+    let last = P::DIMENSIONS - 1;
+    let depth = (max[last] - min[last] + 1) as usize;
+
+    parallel::map_ranges(depth, |slab| {
+        let mut safe_count = 0;
+        let slab = min[last] + slab.start as i64..=min[last] + slab.end as i64 - 1;
+
+        for_each_cell_in(&min, &max, slab, |cell: P, _| {
+            if is_safe(points, threshold as u64, metric, &cell) {
+                safe_count += 1;
+            }
+        });
+
+        safe_count
+    }).iter().sum()
+}
+
+fn is_safe<P, M: Metric<P>>(points: &[P], threshold: u64, metric: &M, cell: &P) -> bool {
+    let mut total_distance = 0;
+
+    for point in points.iter() {
+        total_distance += metric.distance(cell, point);
+
+        // if the current cell is not within the threshold move on to the next one
+        if total_distance >= threshold {
+            return false;
+        }
+    }
+
+    true
 }
 
-fn part_2_bruteforce(points: &[Point], threshold: usize) -> usize {
-    let threshold = threshold as u16;
+/// The smallest and largest coordinate along every axis
+fn bounds<P: Position>(points: &[P]) -> (Vec<i64>, Vec<i64>) {
+    let dims = P::DIMENSIONS;
+
+    let min: Vec<i64> = (0..dims).map(|i| points.iter().map(|p| p.axis(i)).min().expect("no points")).collect();
+    let max: Vec<i64> = (0..dims).map(|i| points.iter().map(|p| p.axis(i)).max().expect("no points")).collect();
 
+    (min, max)
+}
+
+/// Visit every cell of the bounding box of [points], edges included, in any number of dimensions.
+/// [f] also receives whether the cell lies on the edge of the box
+#[cfg(any(not(feature = "parallel"), test))]
+fn for_each_cell<P: Position>(points: &[P], f: impl FnMut(P, bool)) {
+    let (min, max) = bounds(points);
+    let last = P::DIMENSIONS - 1;
+
+    for_each_cell_in(&min, &max, min[last]..=max[last], f)
+}
+
+/// Visit the cells of the box [min]..=[max] whose last axis lies in [slab]. Edges are judged
+/// against the whole box
+fn for_each_cell_in<P: Position>(min: &[i64], max: &[i64], slab: RangeInclusive<i64>, mut f: impl FnMut(P, bool)) {
+    let dims = P::DIMENSIONS;
+    let last = dims - 1;
+
+    if slab.is_empty() {
+        return;
+    }
+
+    let mut cell = min.to_vec();
+    cell[last] = *slab.start();
+
+    loop {
+        let on_edge = (0..dims).any(|i| cell[i] == min[i] || cell[i] == max[i]);
+        f(P::from_axes(&cell), on_edge);
+
+        // advance like an odometer, the first axis turning fastest
+        let mut i = 0;
+        loop {
+            if i == dims {
+                return;
+            }
+
+            let end = if i == last { *slab.end() } else { max[i] };
+            if cell[i] < end {
+                cell[i] += 1;
+                break;
+            }
+
+            cell[i] = min[i];
+            i += 1;
+        }
+    }
+}
+
+/// Count every cell on the plane whose total distance is below [threshold]
+///
+/// The manhattan distance splits per axis: the total distance of (x, y) is `sx(x) + sy(y)` where
+/// `sx(x)` is the sum of `|x - xi|` over all points. Each axis only has O(threshold) positions
+/// with a sum below [threshold], so we list them, sort them, and count the pairs whose sum is still
+/// below [threshold] with two pointers.
+///
+/// ASSUMES AT LEAST ONE POINT, otherwise every cell on the plane qualifies
+fn part_2_separable(coords: &[(i64, i64)], threshold: usize) -> usize {
+    assert!(!coords.is_empty(), "no points");
+
+    let threshold = threshold as i128;
+
+    let mut xs = axis_sums_below(coords.iter().map(|c| c.0).collect(), threshold);
+
+    if xs.is_empty() {
+        return 0;
+    }
+
+    let mut ys = axis_sums_below(coords.iter().map(|c| c.1).collect(), threshold);
+
+    xs.sort_unstable();
+    ys.sort_unstable();
+
+    // for increasing x sums, the number of y sums that fit only shrinks
+    let mut fits = ys.len();
+    let mut count = 0;
+
+    for sx in xs {
+        while fits > 0 && sx + ys[fits - 1] >= threshold {
+            fits -= 1;
+        }
+
+        count += fits;
+    }
+
+    count
+}
+
+/// The sum of distances from each integer position to [coords], for every position where that sum
+/// is below [threshold]
+fn axis_sums_below(mut coords: Vec<i64>, threshold: i128) -> Vec<i128> {
+    coords.sort_unstable();
+
+    let n = coords.len() as i128;
+    let min = coords[0] as i128;
+    let max = coords[coords.len() - 1] as i128;
+
+    // the sum is at least max - min everywhere, and grows by n per step outside [min, max]
+    if max - min >= threshold {
+        return Vec::new();
+    }
+
+    let reach = threshold / n;
+    let (start, end) = (min - reach, max + reach);
+
+    let mut sum: i128 = coords.iter().map(|&c| c as i128 - start).sum();
+    let mut at_or_below = 0;
+    let mut sums = Vec::new();
+
+    for pos in start..=end {
+        if sum < threshold {
+            sums.push(sum);
+        }
+
+        // stepping right moves away from every coord at or left of pos and towards the rest
+        while at_or_below < coords.len() && coords[at_or_below] as i128 <= pos {
+            at_or_below += 1;
+        }
+
+        let at_or_below = at_or_below as i128;
+        sum += at_or_below - (n - at_or_below);
+    }
+
+    sums
+}
+
+/// The point a cell is strictly closest to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Nearest {
+    Unvisited,
+    Point(usize),
+    Tie,
+}
+
+/// Label every cell of the bounding box (edges included) with its nearest point
+///
+/// A multi-source BFS from all points at once: a cell first reached from a single region belongs
+/// to it, a cell reached in the same step from two regions (or from a tie) is a tie. Inside the
+/// box a BFS step is one unit of manhattan distance, so the labels are exact.
+fn voronoi(points: &[Point]) -> Grid<Nearest> {
     let bb = bounding_box(points.iter()).expect("failed to create bounding box");
+    let (w, h) = (bb.width() as usize + 1, bb.height() as usize + 1);
 
-    let w = bb.width();
-    let h = bb.height();
+    let mut labels = Grid::new(w, h, Nearest::Unvisited);
+    let mut steps = Grid::new(w, h, usize::MAX);
+    let mut frontier: Vec<Coord> = Vec::with_capacity(points.len());
 
-    let mut safe_count = 0;
+    for (i, point) in points.iter().enumerate() {
+        let c = (point.x.abs_diff(bb.left) as usize, point.y.abs_diff(bb.top) as usize);
+
+        if labels[c] == Nearest::Unvisited {
+            labels[c] = Nearest::Point(i);
+            steps[c] = 0;
+            frontier.push(c);
+        } else {
+            // duplicate points share their cell
+            labels[c] = Nearest::Tie;
+        }
+    }
 
-    for y in 0..h {
-        'cells: for x in 0..w {
-            let cell = Point { x: x + bb.left, y: y + bb.top };
+    let mut step = 0;
 
-            let mut total_distance = 0;
+    while !frontier.is_empty() {
+        step += 1;
 
-            for point in points.iter() {
-                let dist = cell.manhattan_distance(&point);
+        let mut next = Vec::new();
 
-                total_distance += dist;
+        for &c in &frontier {
+            let label = labels[c];
 
-                // if the current cell is not within the threshold continue to the next one
-                if total_distance >= threshold {
-                    continue 'cells;
+            for n in labels.neighbours(c) {
+                if labels[n] == Nearest::Unvisited {
+                    labels[n] = label;
+                    steps[n] = step;
+                    next.push(n);
+                } else if steps[n] == step && labels[n] != label {
+                    labels[n] = Nearest::Tie;
                 }
             }
-
-            safe_count += 1;
         }
+
+        frontier = next;
     }
 
-    safe_count
+    labels
+}
+
+/// Each cell of the bounding box (edges included) takes its nearest point's colour, cells tied
+/// between points are grey and the points themselves are black. Cells whose total distance is
+/// below [threshold] are lightened to show the safe region.
+fn render_regions(points: &[Point], threshold: usize) -> Grid<Rgb> {
+    const TIE: Rgb = Rgb(96, 96, 96);
+
+    let bb = bounding_box(points.iter()).expect("failed to create bounding box");
+    let labels = voronoi(points);
+    let mut image = Grid::new(labels.width(), labels.height(), Rgb::BLACK);
+
+    for (x, y) in labels.coords() {
+        let cell = Point { x: x as i64 + bb.left, y: y as i64 + bb.top };
+
+        let colour = match labels[(x, y)] {
+            Nearest::Point(i) => image::palette(i),
+            _ => TIE,
+        };
+
+        let total_distance: usize = points.iter()
+            .map(|point| Manhattan.distance(&cell, point) as usize)
+            .sum();
+
+        image[(x, y)] = if total_distance < threshold {
+            colour.mix(Rgb::WHITE, 160)
+        } else {
+            colour
+        };
+    }
+
+    for point in points {
+        image[(point.x.abs_diff(bb.left) as usize, point.y.abs_diff(bb.top) as usize)] = Rgb::BLACK;
+    }
+
+    image
 }
 
 #[derive(Debug)]
 struct Point {
-    x: u16,
-    y: u16,
+    x: i64,
+    y: i64,
 }
 
 impl Point {
-    fn new(x: u16, y: u16) -> Point {
+    fn new(x: i64, y: i64) -> Point {
         Point { x, y }
     }
 
@@ -113,10 +553,10 @@ impl Point {
 
 #[derive(Debug)]
 struct Rect {
-    left: u16,
-    top: u16,
-    right: u16,
-    bottom: u16,
+    left: i64,
+    top: i64,
+    right: i64,
+    bottom: i64,
 }
 
 impl Rect {
@@ -129,12 +569,12 @@ impl Rect {
         }
     }
 
-    fn width(&self) -> u16 {
-        self.right - self.left
+    fn width(&self) -> u64 {
+        self.right.abs_diff(self.left)
     }
 
-    fn height(&self) -> u16 {
-        self.bottom - self.top
+    fn height(&self) -> u64 {
+        self.bottom.abs_diff(self.top)
     }
 
     fn from_point(point: &Point) -> Rect {
@@ -158,39 +598,21 @@ impl Rect {
             self.bottom = point.y
         }
     }
+}
 
-    fn contains(&self, point: &Point) -> bool {
-        if point.x <= self.left {
-            return false;
-        }
-
-        if point.y <= self.top {
-            return false;
-        }
-
-        if point.x >= self.right {
-            return false;
-        }
+impl Position for Point {
+    const DIMENSIONS: usize = 2;
 
-        if point.y >= self.bottom {
-            return false;
+    fn axis(&self, i: usize) -> i64 {
+        match i {
+            0 => self.x,
+            1 => self.y,
+            _ => panic!("no axis {} in 2 dimensions", i),
         }
-
-        true
     }
-}
 
-trait ManhattanDistance {
-    type Output;
-
-    fn manhattan_distance(&self, other: &Self) -> Self::Output;
-}
-
-impl ManhattanDistance for Point {
-    type Output = u16;
-
-    fn manhattan_distance(&self, other: &Self) -> Self::Output {
-        self.x.sub_abs(other.x) + self.y.sub_abs(other.y)
+    fn from_axes(axes: &[i64]) -> Point {
+        Point { x: axes[0], y: axes[1] }
     }
 }
 
@@ -205,22 +627,29 @@ fn bounding_box<'a>(mut points: impl Iterator<Item=&'a Point>) -> Option<Rect> {
         })
 }
 
-fn parse_point(s: &str) -> Point {
+fn parse_point(s: &str) -> Option<Point> {
     let mut iter = s.split(", ");
-    let x = iter.next().expect("no x value").parse().expect("failed to parse x");
-    let y = iter.next().expect("no y value").parse().expect("failed to parse y");
-    Point { x, y }
+    let x = iter.next()?.trim().parse().ok()?;
+    let y = iter.next()?.trim().parse().ok()?;
+    Some(Point { x, y })
 }
 
-fn parse_points(s: &str) -> Vec<Point> {
+/// Every point in [s], one per line, or the first line that isn't one
+fn try_parse_points(s: &str) -> Result<Vec<Point>, String> {
     s.lines()
-        .map(parse_point)
+        .enumerate()
+        .map(|(i, line)| parse_point(line).ok_or_else(|| format!("line {}: expected a point like `1, 2`", i + 1)))
         .collect()
 }
 
+fn parse_points(s: &str) -> Vec<Point> {
+    try_parse_points(s).expect("failed to parse points")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::super::metric::Point3;
 
     const SAMPLE_INPUT: &str = include_str!("sample.txt");
     const SAMPLE_THRESHOLD: usize = 32;
@@ -235,6 +664,402 @@ mod tests {
         assert_eq!(16, solve_part_2(SAMPLE_INPUT, SAMPLE_THRESHOLD))
     }
 
+    #[test]
+    fn stats_sample() {
+        assert_eq!("\
+Points: 6
+Bounding box: 1, 1 to 8, 9
+Manhattan: largest finite area 17, 72 cells in the box below 10000
+Chebyshev: largest finite area 10, 72 cells in the box below 10000
+Squared euclidean: largest finite area 16, 72 cells in the box below 10000
+", stats(Some(SAMPLE_INPUT)).unwrap());
+    }
+
+    #[test]
+    fn stats_reports_bad_input() {
+        assert_eq!(Err("no points".to_string()), stats(Some("")));
+        assert_eq!(Err("line 2: expected a point like `1, 2`".to_string()), stats(Some("1, 2\n3 4\n")));
+    }
+
+    #[test]
+    fn render_marks_regions_ties_and_safe_cells() {
+        let points = parse_points(SAMPLE_INPUT);
+        let image = render_regions(&points, SAMPLE_THRESHOLD);
+
+        // the sample spans x 1..=8 and y 1..=9
+        assert_eq!(8, image.width());
+        assert_eq!(9, image.height());
+
+        // points are black
+        assert_eq!(Rgb::BLACK, image[(0, 0)]);
+
+        // (1, 2) is only nearest to A, (5, 1) is tied between A and E
+        assert_eq!(image::palette(0), image[(0, 1)]);
+        assert_eq!(Rgb(96, 96, 96), image[(4, 0)]);
+
+        // (5, 4) belongs to E and is in the safe region
+        assert_eq!(image::palette(4).mix(Rgb::WHITE, 160), image[(4, 3)]);
+    }
+
+    #[test]
+    fn voronoi_labels_sample() {
+        let labels = voronoi(&parse_points(SAMPLE_INPUT));
+
+        // the sample's grid from the puzzle, shifted so (1, 1) is the origin
+        let expected = "\
+aaaa.ccc
+aaddeccc
+adddeccC
+.dDdeecc
+b.deEeec
+Bb.eeee.
+bb.eeeff
+bb.eefff
+bb.ffffF";
+
+        let actual: Vec<String> = (0..labels.height())
+            .map(|y| {
+                (0..labels.width())
+                    .map(|x| match labels[(x, y)] {
+                        Nearest::Point(i) => (b'a' + i as u8) as char,
+                        _ => '.',
+                    })
+                    .collect()
+            })
+            .collect();
+
+        assert_eq!(expected.to_lowercase().replace('\n', ""), actual.concat());
+    }
+
+    fn points(coords: &[(i64, i64)]) -> Vec<Point> {
+        coords.iter().map(|&(x, y)| Point { x, y }).collect()
+    }
+
+    fn assert_largest_finite_area(expected: usize, coords: &[(i64, i64)]) {
+        let points = points(coords);
+        assert_eq!(Ok(expected), part_1_bruteforce(&points, &Manhattan), "brute force");
+        assert_eq!(expected, part_1_flood_fill(&points), "flood fill");
+        assert_eq!(expected, largest_finite_area_in_grown_box(&axes(&points), 5, manhattan), "grown box");
+    }
+
+    // The corners of a 20x20 box around a cross of five points. The largest finite region
+    // among them covers 18 cells.
+    const WALLED_IN: [(i64, i64); 9] = [
+        (0, 0), (20, 0), (0, 20), (20, 20),
+        (10, 10), (10, 7), (10, 13), (7, 10), (13, 10),
+    ];
+
+    #[test]
+    fn seed_inside_box_near_top_edge_is_infinite() {
+        // (10, 1) lies strictly inside the box but owns (10, 0) on the top edge, so its region
+        // is unbounded. Judging finiteness by the seed position alone answers 42 (its clipped
+        // area) instead of 18.
+        let mut coords = WALLED_IN.to_vec();
+        coords.push((10, 1));
+        assert_largest_finite_area(18, &coords);
+    }
+
+    #[test]
+    fn seed_inside_box_near_right_edge_is_infinite() {
+        // (19, 10) owns (20, 10) on the right edge. The right column is also the one a
+        // `0..w` scan never visits, so both the seed heuristic and the edge scan must be fixed.
+        let mut coords = WALLED_IN.to_vec();
+        coords.push((19, 10));
+        assert_largest_finite_area(18, &coords);
+    }
+
+    #[test]
+    fn seed_inside_box_near_bottom_edge_is_infinite() {
+        // every region reaches the edge: the answer is 0, the heuristic says 29
+        assert_largest_finite_area(0, &[(0, 0), (10, 0), (0, 10), (10, 10), (5, 9)]);
+    }
+
+    #[test]
+    fn seed_in_the_middle_is_finite() {
+        assert_largest_finite_area(41, &[(0, 0), (10, 0), (0, 10), (10, 10), (5, 5)]);
+    }
+
+    #[test]
+    fn single_point_is_infinite() {
+        assert_largest_finite_area(0, &[(3, 3)]);
+    }
+
+    /// The coordinates of each point, one entry per axis
+    fn axes<P: Position>(points: &[P]) -> Vec<Vec<i64>> {
+        points.iter().map(|p| (0..P::DIMENSIONS).map(|i| p.axis(i)).collect()).collect()
+    }
+
+    /// Every cell of the bounding box of [points] grown by [margin] on each side, with whether it
+    /// lies on the edge of the grown box
+    fn grown_box(points: &[Vec<i64>], margin: i64) -> Vec<(Vec<i64>, bool)> {
+        let dims = points[0].len();
+        let min: Vec<i64> = (0..dims).map(|i| points.iter().map(|p| p[i]).min().unwrap() - margin).collect();
+        let max: Vec<i64> = (0..dims).map(|i| points.iter().map(|p| p[i]).max().unwrap() + margin).collect();
+
+        let mut cells = vec![(Vec::new(), false)];
+        for i in 0..dims {
+            let (min, max) = (min[i], max[i]);
+            cells = cells.into_iter()
+                .flat_map(|(cell, edge)| (min..=max).map(move |x| {
+                    let mut cell = cell.clone();
+                    cell.push(x);
+                    (cell, edge || x == min || x == max)
+                }))
+                .collect();
+        }
+        cells
+    }
+
+    fn manhattan(a: &[i64], b: &[i64]) -> u64 {
+        a.iter().zip(b).map(|(x, y)| (x - y).unsigned_abs()).sum()
+    }
+
+    fn chebyshev(a: &[i64], b: &[i64]) -> u64 {
+        a.iter().zip(b).map(|(x, y)| (x - y).unsigned_abs()).max().unwrap()
+    }
+
+    fn squared_euclidean(a: &[i64], b: &[i64]) -> u64 {
+        a.iter().zip(b).map(|(x, y)| ((x - y) * (x - y)) as u64).sum()
+    }
+
+    /// Part 1 under [distance], measuring regions in a box grown by [margin] and calling a region
+    /// infinite only if it reaches the edge of the grown box
+    fn largest_finite_area_in_grown_box(points: &[Vec<i64>], margin: i64, distance: fn(&[i64], &[i64]) -> u64) -> usize {
+        let mut areas = vec![0; points.len()];
+        let mut infinite = vec![false; points.len()];
+
+        for (cell, edge) in grown_box(points, margin) {
+            let distances: Vec<u64> = points.iter().map(|p| distance(p, &cell)).collect();
+            let min = *distances.iter().min().unwrap();
+            if distances.iter().filter(|&&d| d == min).count() == 1 {
+                let i = distances.iter().position(|&d| d == min).unwrap();
+                areas[i] += 1;
+                infinite[i] |= edge;
+            }
+        }
+
+        (0..points.len()).filter(|&i| !infinite[i]).map(|i| areas[i]).max().unwrap_or(0)
+    }
+
+    /// Part 2 counted cell by cell over the bounding box alone, like [part_2_bruteforce]
+    fn safe_cells_in_box(points: &[Vec<i64>], threshold: u64, distance: fn(&[i64], &[i64]) -> u64) -> usize {
+        grown_box(points, 0).iter()
+            .filter(|(cell, _)| points.iter().map(|p| distance(p, cell)).sum::<u64>() < threshold)
+            .count()
+    }
+
+    #[test]
+    fn metrics_on_sample() {
+        let points = parse_points(SAMPLE_INPUT);
+        let axes = axes(&points);
+
+        assert_eq!(Ok(17), part_1_bruteforce(&points, &Manhattan));
+        assert_eq!(17, largest_finite_area_in_grown_box(&axes, 10, manhattan));
+        assert_eq!(Ok(10), part_1_bruteforce(&points, &Chebyshev));
+        assert_eq!(10, largest_finite_area_in_grown_box(&axes, 32, chebyshev));
+        assert_eq!(Ok(16), part_1_bruteforce(&points, &SquaredEuclidean));
+        assert_eq!(16, largest_finite_area_in_grown_box(&axes, 32, squared_euclidean));
+
+        assert_eq!(16, part_2_bruteforce(&points, 32, &Manhattan));
+        assert_eq!(69, part_2_bruteforce(&points, 32, &Chebyshev));
+        assert_eq!(56, part_2_bruteforce(&points, 200, &SquaredEuclidean));
+
+        assert_eq!(16, safe_cells_in_box(&axes, 32, manhattan));
+        assert_eq!(69, safe_cells_in_box(&axes, 32, chebyshev));
+        assert_eq!(56, safe_cells_in_box(&axes, 200, squared_euclidean));
+    }
+
+    #[test]
+    fn closure_metric() {
+        let points = parse_points(SAMPLE_INPUT);
+        let manhattan = |a: &Point, b: &Point| Manhattan.distance(a, b);
+
+        // a closure can't vouch for growing uniformly outside the box, so the box is grown until
+        // its regions settle
+        assert_eq!(Ok(17), part_1_bruteforce(&points, &manhattan));
+        assert_eq!(16, part_2_bruteforce(&points, 32, &manhattan));
+    }
+
+    #[test]
+    fn regions_that_never_settle() {
+        // the second point owns the cells at odd y on the line x = 0, so its region is finite
+        // whenever the box's top and bottom edges land on even rows and grows with the box
+        let points = points(&[(0, 0), (1, 0)]);
+        let rows = |cell: &Point, point: &Point| if point.x == 1 && cell.x == 0 && cell.y % 2 != 0 { 0 } else { 1 };
+
+        assert_eq!(Err(UNSETTLED_REGIONS), part_1_bruteforce(&points, &rows));
+    }
+
+    /// The corners and face centres of a 10x10x10 cube, plus its centre
+    fn cube() -> Vec<Point3> {
+        let mut points = Vec::new();
+
+        for &x in &[0, 10] {
+            for &y in &[0, 10] {
+                for &z in &[0, 10] {
+                    points.push(Point3 { x, y, z });
+                }
+            }
+        }
+
+        for &(x, y, z) in &[(5, 5, 5), (5, 5, 0), (5, 5, 10), (5, 0, 5), (5, 10, 5), (0, 5, 5), (10, 5, 5)] {
+            points.push(Point3 { x, y, z });
+        }
+
+        points
+    }
+
+    #[test]
+    fn metrics_in_3d() {
+        let points = cube();
+
+        let axes = axes(&points);
+
+        // only the centre's region is enclosed, but under chebyshev a larger one closes off outside
+        // the bounding box
+        assert_eq!(Ok(125), part_1_bruteforce(&points, &Manhattan));
+        assert_eq!(125, largest_finite_area_in_grown_box(&axes, 3, manhattan));
+        assert_eq!(Ok(239), part_1_bruteforce(&points, &Chebyshev));
+        assert_eq!(239, largest_finite_area_in_grown_box(&axes, 20, chebyshev));
+        assert_eq!(Ok(125), part_1_bruteforce(&points, &SquaredEuclidean));
+        assert_eq!(125, largest_finite_area_in_grown_box(&axes, 20, squared_euclidean));
+
+        assert_eq!(895, part_2_bruteforce(&points, 200, &Manhattan));
+        assert_eq!(523, part_2_bruteforce(&points, 100, &Chebyshev));
+        assert_eq!(1143, part_2_bruteforce(&points, 1500, &SquaredEuclidean));
+        assert_eq!(1331, part_2_bruteforce(&points, 2000, &SquaredEuclidean));
+
+        assert_eq!(895, safe_cells_in_box(&axes, 200, manhattan));
+        assert_eq!(523, safe_cells_in_box(&axes, 100, chebyshev));
+        assert_eq!(1143, safe_cells_in_box(&axes, 1500, squared_euclidean));
+        assert_eq!(1331, safe_cells_in_box(&axes, 2000, squared_euclidean));
+    }
+
+    #[test]
+    fn flat_3d_points_are_all_infinite() {
+        // every cell of a flat box lies on its top and bottom faces
+        let points: Vec<Point3> = parse_points(SAMPLE_INPUT).iter()
+            .map(|p| Point3 { x: p.x, y: p.y, z: 0 })
+            .collect();
+
+        assert_eq!(Ok(0), part_1_bruteforce(&points, &Manhattan));
+        assert_eq!(0, largest_finite_area_in_grown_box(&axes(&points), 2, manhattan));
+        assert_eq!(16, part_2_bruteforce(&points, 32, &Manhattan));
+    }
+
+    #[test]
+    fn flood_fill_agrees_with_brute_force() {
+        let points = parse_points(INPUT);
+        assert_eq!(Ok(part_1_flood_fill(&points)), part_1_bruteforce(&points, &Manhattan));
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn parallel_brute_force_agrees_with_serial() {
+        let points = parse_points(INPUT);
+        assert_eq!(Ok(5358), part_1_bruteforce_parallel(&points, &Manhattan));
+        assert_eq!(37093, part_2_bruteforce_parallel(&points, INPUT_THRESHOLD, &Manhattan));
+
+        let points = cube();
+        assert_eq!(Ok(125), part_1_bruteforce_parallel(&points, &Manhattan));
+        assert_eq!(Ok(239), part_1_bruteforce_parallel(&points, &Chebyshev));
+        assert_eq!(1143, part_2_bruteforce_parallel(&points, 1500, &SquaredEuclidean));
+
+        // a single slab along z
+        let points: Vec<Point3> = parse_points(SAMPLE_INPUT).iter()
+            .map(|p| Point3 { x: p.x, y: p.y, z: 0 })
+            .collect();
+        assert_eq!(Ok(0), part_1_bruteforce_parallel(&points, &Manhattan));
+        assert_eq!(16, part_2_bruteforce_parallel(&points, 32, &Manhattan));
+    }
+
+    /// Every cell within [threshold] of the bounding box, checked one by one
+    fn part_2_reference(coords: &[(i64, i64)], threshold: usize) -> usize {
+        let t = threshold as i64;
+        let left = coords.iter().map(|c| c.0).min().unwrap() - t;
+        let right = coords.iter().map(|c| c.0).max().unwrap() + t;
+        let top = coords.iter().map(|c| c.1).min().unwrap() - t;
+        let bottom = coords.iter().map(|c| c.1).max().unwrap() + t;
+
+        let mut count = 0;
+        for y in top..=bottom {
+            for x in left..=right {
+                let total: i64 = coords.iter().map(|c| (c.0 - x).abs() + (c.1 - y).abs()).sum();
+                if total < t { count += 1 }
+            }
+        }
+        count
+    }
+
+    fn coords(input: &str) -> Vec<(i64, i64)> {
+        parse_points(input).iter().map(|p| (p.x, p.y)).collect()
+    }
+
+    #[test]
+    fn separable_agrees_with_reference() {
+        let sample = coords(SAMPLE_INPUT);
+
+        for threshold in (0..120).step_by(7) {
+            assert_eq!(part_2_reference(&sample, threshold), part_2_separable(&sample, threshold), "threshold {}", threshold);
+        }
+
+        let pair = [(0, 0), (3, -2)];
+        for threshold in 0..20 {
+            assert_eq!(part_2_reference(&pair, threshold), part_2_separable(&pair, threshold), "threshold {}", threshold);
+        }
+    }
+
+    #[test]
+    fn separable_counts_cells_outside_the_bounding_box() {
+        // a single point's box is a single cell, but |x| + |y| < 3 holds for 13 cells
+        assert_eq!(1, part_2_bruteforce(&points(&[(5, 5)]), 3, &Manhattan));
+        assert_eq!(13, part_2_separable(&[(5, 5)], 3));
+
+        // with a large threshold the sample's safe region spills past its box
+        let threshold = 100;
+        assert!(part_2_bruteforce(&parse_points(SAMPLE_INPUT), threshold, &Manhattan) < part_2_separable(&coords(SAMPLE_INPUT), threshold));
+    }
+
+    #[test]
+    fn separable_handles_thresholds_beyond_u16() {
+        // the cells with |x| + |y| <= t - 1 form a diamond of 2(t - 1)^2 + 2(t - 1) + 1 cells
+        let t = 100_000;
+        let expected = 2 * (t - 1) * (t - 1) + 2 * (t - 1) + 1;
+        assert_eq!(expected, part_2_separable(&[(0, 0)], t));
+    }
+
+    #[test]
+    fn separable_handles_wide_coordinates() {
+        let far = [(i64::MIN, i64::MAX)];
+        assert_eq!(13, part_2_separable(&far, 3));
+
+        let spread = [(i64::MIN, 0), (i64::MAX, 0)];
+        assert_eq!(0, part_2_separable(&spread, usize::MAX));
+
+        let shifted: Vec<(i64, i64)> = coords(SAMPLE_INPUT).iter()
+            .map(|&(x, y)| (x + 1_000_000_000_000, y - 1_000_000_000_000))
+            .collect();
+        assert_eq!(16, part_2_separable(&shifted, SAMPLE_THRESHOLD));
+    }
+
+    #[test]
+    fn coordinates_beyond_u16() {
+        let point = parse_point("-70000, 5000000000").unwrap();
+        assert_eq!((-70_000, 5_000_000_000), (point.x, point.y));
+
+        let shifted: String = coords(SAMPLE_INPUT).iter()
+            .map(|&(x, y)| format!("{}, {}\n", x - 70_000, y + 5_000_000_000))
+            .collect();
+        assert_eq!(17, solve_part_1(&shifted));
+        assert_eq!(16, solve_part_2(&shifted, SAMPLE_THRESHOLD));
+    }
+
+    #[test]
+    fn separable_agrees_with_brute_force() {
+        let points = parse_points(INPUT);
+        assert_eq!(part_2_bruteforce(&points, INPUT_THRESHOLD, &Manhattan), solve_part_2(INPUT, INPUT_THRESHOLD));
+    }
+
     #[test]
     fn part_1_is_correct() {
         assert_eq!(5358, solve_part_1(INPUT))
@@ -257,13 +1082,41 @@ mod bench {
     #[bench]
     fn part_1_bf(b: &mut Bencher) {
         let points = parse_points(INPUT);
-        b.iter(|| { part_1_bruteforce(&points) });
+        b.iter(|| { part_1_bruteforce(&points, &Manhattan) });
+    }
+
+    #[bench]
+    #[cfg(feature = "parallel")]
+    fn part_1_bf_parallel(b: &mut Bencher) {
+        let points = parse_points(INPUT);
+        b.iter(|| { part_1_bruteforce_parallel(&points, &Manhattan) });
+    }
+
+    #[bench]
+    fn part_1_flood(b: &mut Bencher) {
+        let points = parse_points(INPUT);
+        b.iter(|| { part_1_flood_fill(&points) });
     }
 
     #[bench]
     fn part_2_bf(b: &mut Bencher) {
         let points = parse_points(INPUT);
-        b.iter(|| { part_2_bruteforce(&points, INPUT_THRESHOLD) });
+        b.iter(|| { part_2_bruteforce(&points, INPUT_THRESHOLD, &Manhattan) });
+    }
+
+    #[bench]
+    #[cfg(feature = "parallel")]
+    fn part_2_bf_parallel(b: &mut Bencher) {
+        let points = parse_points(INPUT);
+        b.iter(|| { part_2_bruteforce_parallel(&points, INPUT_THRESHOLD, &Manhattan) });
+    }
+
+    #[bench]
+    fn part_2_separable_sums(b: &mut Bencher) {
+        let coords: Vec<(i64, i64)> = parse_points(INPUT).iter()
+            .map(|p| (p.x, p.y))
+            .collect();
+        b.iter(|| { part_2_separable(&coords, INPUT_THRESHOLD) });
     }
 
 }
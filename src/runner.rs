@@ -0,0 +1,193 @@
+//! Runs the solutions on a small pool of std threads and reports them in order
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// One part of one day
+#[derive(Clone, Copy)]
+pub struct Task {
+    pub day: u8,
+    pub part: u8,
+    pub run: fn() -> String,
+}
+
+/// What a task printed and how long it took. A task that failed prints why instead
+pub struct Outcome {
+    pub output: String,
+    pub elapsed: Duration,
+    pub failed: bool,
+}
+
+/// Run [task], catching a panic so it fails only that task
+pub fn run_task(task: &Task) -> Outcome {
+    let now = Instant::now();
+
+    match panic::catch_unwind(AssertUnwindSafe(task.run)) {
+        Ok(output) => Outcome { output, elapsed: now.elapsed(), failed: false },
+        Err(payload) => Outcome {
+            output: format!("Problem {}, Part {}: panicked ({})", task.day, task.part, panic_message(&*payload)),
+            elapsed: now.elapsed(),
+            failed: true,
+        },
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message,
+        None => payload.downcast_ref::<String>().map_or("unknown cause", String::as_str),
+    }
+}
+
+/// Runs [tasks] on up to [jobs] threads. [report] is called once per task, in the order of
+/// [tasks], as soon as that task and every task before it have finished.
+pub fn run_parallel(tasks: &[Task], jobs: usize, mut report: impl FnMut(&Task, &Outcome)) {
+    let jobs = jobs.max(1).min(tasks.len());
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            let next = &next;
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                match tasks.get(i) {
+                    Some(task) => {
+                        if sender.send((i, run_task(task))).is_err() {
+                            return;
+                        }
+                    }
+                    None => return,
+                }
+            });
+        }
+        drop(sender);
+
+        let mut finished: Vec<Option<Outcome>> = tasks.iter().map(|_| None).collect();
+        let mut reported = 0;
+
+        for (i, outcome) in receiver {
+            finished[i] = Some(outcome);
+
+            while let Some(Some(outcome)) = finished.get(reported) {
+                report(&tasks[reported], outcome);
+                reported += 1;
+            }
+        }
+    });
+}
+
+pub fn millis(elapsed: Duration) -> f64 {
+    elapsed.as_secs_f64() * 1_000f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slow() -> String {
+        thread::sleep(Duration::from_millis(50));
+        "slow".to_string()
+    }
+
+    fn fast() -> String {
+        "fast".to_string()
+    }
+
+    fn tasks() -> Vec<Task> {
+        vec![
+            Task { day: 1, part: 1, run: slow },
+            Task { day: 1, part: 2, run: fast },
+            Task { day: 2, part: 1, run: slow },
+            Task { day: 2, part: 2, run: fast },
+        ]
+    }
+
+    #[test]
+    fn reports_in_task_order() {
+        let tasks = tasks();
+        let mut seen = Vec::new();
+
+        run_parallel(&tasks, 4, |task, outcome| {
+            seen.push((task.day, task.part, outcome.output.clone()))
+        });
+
+        assert_eq!(vec![
+            (1, 1, "slow".to_string()),
+            (1, 2, "fast".to_string()),
+            (2, 1, "slow".to_string()),
+            (2, 2, "fast".to_string()),
+        ], seen);
+    }
+
+    static ARRIVED: AtomicUsize = AtomicUsize::new(0);
+
+    /// Wait for the other [meet] task to arrive, giving up after a generous while rather than
+    /// hanging the test run if the tasks never overlap
+    fn meet() -> String {
+        ARRIVED.fetch_add(1, Ordering::SeqCst);
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while ARRIVED.load(Ordering::SeqCst) < 2 {
+            if Instant::now() > deadline {
+                return "alone".to_string();
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        "met".to_string()
+    }
+
+    #[test]
+    fn tasks_run_at_the_same_time() {
+        let tasks = vec![Task { day: 1, part: 1, run: meet }, Task { day: 1, part: 2, run: meet }];
+        let mut outputs = Vec::new();
+
+        run_parallel(&tasks, 2, |_, outcome| outputs.push(outcome.output.clone()));
+
+        assert_eq!(vec!["met", "met"], outputs);
+    }
+
+    fn panics() -> String {
+        panic!("out of {}", "fuel")
+    }
+
+    #[test]
+    fn a_panic_fails_only_its_task() {
+        let tasks = vec![
+            Task { day: 1, part: 1, run: fast },
+            Task { day: 1, part: 2, run: panics },
+            Task { day: 2, part: 1, run: fast },
+        ];
+        let mut seen = Vec::new();
+
+        run_parallel(&tasks, 2, |_, outcome| seen.push((outcome.output.clone(), outcome.failed)));
+
+        assert_eq!(vec![
+            ("fast".to_string(), false),
+            ("Problem 1, Part 2: panicked (out of fuel)".to_string(), true),
+            ("fast".to_string(), false),
+        ], seen);
+
+        fn panics_with_a_str() -> String {
+            panic!("static")
+        }
+        let outcome = run_task(&Task { day: 3, part: 1, run: panics_with_a_str });
+        assert_eq!("Problem 3, Part 1: panicked (static)", outcome.output);
+    }
+
+    #[test]
+    fn more_jobs_than_tasks() {
+        let tasks = vec![Task { day: 1, part: 1, run: fast }];
+        let mut count = 0;
+
+        run_parallel(&tasks, 16, |_, _| count += 1);
+        run_parallel(&[], 16, |_, _| count += 1);
+
+        assert_eq!(1, count);
+    }
+}